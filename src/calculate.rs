@@ -1,230 +1,1199 @@
-use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Weekday};
+use std::{collections::BTreeMap, fmt};
+
+use chrono::{Datelike, Duration, IsoWeek, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
 
 use crate::{
-    database::Database,
-    time::{calculate_shift_time, parse_naivedatetime_from_str, SaleryPeriod},
-    wage_bonuses::WageAndBonuses,
+    error::Result,
+    shift::{Shift, ShiftStatus},
+    store::ShiftStore,
+    time::{calculate_shift_time, salery_period_containing, Interval, SaleryPeriod},
+    wage_bonuses::{Bonus, BreakDeduction, OvertimeRule, WageAndBonuses},
 };
 
+/// What a line on the payslip pays for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKind {
+    /// Every hour worked, at the base rate
+    Base,
+    /// The hours covered by a bonus, including the bonuses for holidays
+    Bonus,
+    /// The part of the overtime multiplier above the base rate
+    Overtime,
+}
+
+/// A single line on the payslip, eg. the hours paid at the base rate or the hours covered by a bonus
 pub struct SaleryEntry {
+    kind: EntryKind,
+    description: String,
     duration: Duration,
     bonus_pr_hour: f64,
 }
 
 impl SaleryEntry {
-    fn new(duration: Duration, bonus: f64) -> Self {
+    fn new(kind: EntryKind, description: String, duration: Duration, bonus: f64) -> Self {
         Self {
+            kind,
+            description,
             duration,
             bonus_pr_hour: bonus,
         }
     }
+
+    pub fn kind(&self) -> EntryKind {
+        self.kind
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn bonus_pr_hour(&self) -> f64 {
+        self.bonus_pr_hour
+    }
+
+    pub fn salery(&self) -> f64 {
+        self.duration.num_seconds() as f64 * self.bonus_pr_hour / 3600.0
+    }
+}
+
+/// All the salery entries produced by a single shift
+pub struct ShiftSalery {
+    shift_start: NaiveDateTime,
+    shift_end: NaiveDateTime,
+    break_duration: Duration,
+    worked: Duration,
+    entries: Vec<SaleryEntry>,
+}
+
+impl ShiftSalery {
+    pub fn start(&self) -> NaiveDateTime {
+        self.shift_start
+    }
+
+    pub fn end(&self) -> NaiveDateTime {
+        self.shift_end
+    }
+
+    /// The unpaid break taken during the shift
+    pub fn break_duration(&self) -> Duration {
+        self.break_duration
+    }
+
+    /// The part of the shift that lies inside the salery period, without the unpaid break
+    pub fn worked(&self) -> Duration {
+        self.worked
+    }
+
+    pub fn entries(&self) -> &Vec<SaleryEntry> {
+        &self.entries
+    }
+
+    pub fn salery(&self) -> f64 {
+        self.entries
+            .iter()
+            .fold(0.0, |total, entry| total + entry.salery())
+    }
+}
+
+/// The itemized salery of a salery period, shift by shift
+pub struct Payslip {
+    shifts: Vec<ShiftSalery>,
+    overtime: Vec<SaleryEntry>,
+}
+
+impl Payslip {
+    pub fn shifts(&self) -> &Vec<ShiftSalery> {
+        &self.shifts
+    }
+
+    /// Overtime is worked out from all the shifts together, so it isn't part of any single shift
+    pub fn overtime(&self) -> &Vec<SaleryEntry> {
+        &self.overtime
+    }
+
+    pub fn salery(&self) -> f64 {
+        self.shifts
+            .iter()
+            .fold(0.0, |total, shift| total + shift.salery())
+            + self
+                .overtime
+                .iter()
+                .fold(0.0, |total, entry| total + entry.salery())
+    }
+
+    pub fn duration_worked(&self) -> Duration {
+        self.shifts.iter().map(|shift| shift.worked()).sum()
+    }
+
+    /// Sums up the entries of every shift, so there is one line for the base rate and one for each bonus
+    pub fn totals(&self) -> Vec<SaleryEntry> {
+        merge_entries(self.shifts.iter().flat_map(|shift| shift.entries.iter()))
+    }
+}
+
+impl fmt::Display for Payslip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for shift in self.shifts() {
+            writeln!(
+                f,
+                "Shift {} - {}{}{}",
+                shift.start().format("%Y-%m-%d %H:%M"),
+                shift.end().format("%Y-%m-%d %H:%M"),
+                if shift.break_duration() > Duration::zero() {
+                    format!(
+                        " ({} minutes unpaid break)",
+                        shift.break_duration().num_minutes()
+                    )
+                } else {
+                    String::new()
+                },
+                if shift.worked() + shift.break_duration()
+                    < calculate_shift_time(shift.start(), shift.end())
+                {
+                    " (only the part inside the salery period is paid)"
+                } else {
+                    ""
+                }
+            )?;
+            write_entries(f, shift.entries())?;
+            writeln!(f, "    {:<58}{:>12.2} kr.\n", "shift total", shift.salery())?;
+        }
+
+        if !self.overtime.is_empty() {
+            writeln!(f, "Overtime")?;
+            write_entries(f, self.overtime())?;
+            writeln!(f)?;
+        }
+
+        writeln!(f, "Period total")?;
+        write_entries(f, &self.totals())?;
+        write_entries(f, self.overtime())?;
+        write!(f, "    {:<58}{:>12.2} kr.", "total", self.salery())
+    }
 }
 
-pub fn salery_entries_from_period(
-    database: &Database,
+fn write_entries(f: &mut fmt::Formatter<'_>, entries: &[SaleryEntry]) -> fmt::Result {
+    for entry in entries {
+        writeln!(
+            f,
+            "    {:<32}{:>10} x {:>9.2} kr. ={:>12.2} kr.",
+            entry.description(),
+            format_duration(entry.duration()),
+            entry.bonus_pr_hour(),
+            entry.salery()
+        )?;
+    }
+    Ok(())
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!(
+        "{}h {:02}m",
+        duration.num_hours(),
+        duration.num_minutes() - duration.num_hours() * 60
+    )
+}
+
+/// Merges entries of the same kind, description and rate into one, keeping the order they first appeared in
+fn merge_entries<'a, I: Iterator<Item = &'a SaleryEntry>>(entries: I) -> Vec<SaleryEntry> {
+    let mut merged: Vec<SaleryEntry> = Vec::new();
+
+    for entry in entries {
+        match merged.iter_mut().find(|m| {
+            m.kind == entry.kind
+                && m.description == entry.description
+                && m.bonus_pr_hour == entry.bonus_pr_hour
+        }) {
+            Some(m) => m.duration += entry.duration,
+            None => merged.push(SaleryEntry::new(
+                entry.kind,
+                entry.description.clone(),
+                entry.duration,
+                entry.bonus_pr_hour,
+            )),
+        }
+    }
+
+    merged
+}
+
+/// Only the shifts with one of the statuses are paid, eg. just the worked shifts for what has been earned so far
+pub fn payslip_from_period(
+    store: &dyn ShiftStore,
+    job: &str,
     wage_and_bonus: &WageAndBonuses,
     salery_period: &SaleryPeriod,
-) -> Vec<SaleryEntry> {
-    let query = format!(
-        "Select * from {} where shift_start <= :shift_end and shift_end > :shift_start",
-        database.table()
+    statuses: &[ShiftStatus],
+) -> Result<Payslip> {
+    // overtime is worked out over whole weeks, so the shifts of the weeks the period starts and ends in are read too
+    let first_week = salery_period.start().date().week(Weekday::Mon);
+    let last_week = (salery_period.end() - Duration::seconds(1))
+        .date()
+        .week(Weekday::Mon);
+    let weeks = SaleryPeriod::new(
+        first_week.first_day().and_time(NaiveTime::MIN),
+        (last_week.last_day() + Duration::days(1)).and_time(NaiveTime::MIN),
     );
 
-    let mut salery_entries = Vec::new();
-
-    for row in database
-        .prepare(query)
-        .unwrap()
+    let shifts: Vec<Shift> = store
+        .shifts_in_range(job, Some(&weeks))?
         .into_iter()
-        .bind(
-            &[
-                (
-                    ":shift_start",
-                    format!("{}", salery_period.start()).as_str(),
-                ),
-                (":shift_end", format!("{}", salery_period.end()).as_str()), //"2023-11-21"
-            ][..],
-        ) // change this, so it is the current salery period
-        .expect("Couldn't prepare statement for gettign shifts when calculating salery")
-        .map(|row| row.unwrap())
-    {
-        let shift_start = parse_naivedatetime_from_str(row.read::<&str, _>("shift_start")).unwrap();
-        let shift_end = parse_naivedatetime_from_str(row.read::<&str, _>("shift_end")).unwrap();
-
-        // checking if shift crosses midnight
-        if shift_start.date() != shift_end.date() {
-            let shift1_start = shift_start;
-            let shift1_end = NaiveDateTime::new(
-                shift_start.date(),
-                NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
-            );
-            let shift2_start =
-                NaiveDateTime::new(shift_end.date(), NaiveTime::from_hms_opt(0, 0, 0).unwrap());
-            let shift2_end = shift_end;
-
-            if shift1_start >= salery_period.start() {
-                salery_entries.extend(salery_entries_from_shift(
-                    &wage_and_bonus,
-                    shift1_start,
-                    shift1_end,
-                ));
+        .filter(|shift| statuses.contains(&shift.status()))
+        .collect();
+
+    Ok(payslip_from_shifts(&shifts, wage_and_bonus, salery_period))
+}
+
+/// What has been earned so far
+pub const EARNED: &[ShiftStatus] = &[ShiftStatus::Worked];
+/// What will have been earned once the planned shifts are worked
+pub const PROJECTED: &[ShiftStatus] = &[ShiftStatus::Worked, ShiftStatus::Planned];
+
+/// What has been earned in a salery period, and what will have been earned once its planned shifts are worked
+pub struct PeriodForecast {
+    salery_period: SaleryPeriod,
+    earned: f64,
+    projected: f64,
+}
+
+impl PeriodForecast {
+    pub fn salery_period(&self) -> &SaleryPeriod {
+        &self.salery_period
+    }
+
+    pub fn earned(&self) -> f64 {
+        self.earned
+    }
+
+    /// The pay of the planned shifts
+    pub fn planned(&self) -> f64 {
+        self.projected - self.earned
+    }
+
+    pub fn projected(&self) -> f64 {
+        self.projected
+    }
+}
+
+/// The forecast of the given number of salery periods, starting with the first one
+pub fn forecast(
+    store: &dyn ShiftStore,
+    job: &str,
+    wage_and_bonus: &WageAndBonuses,
+    first: SaleryPeriod,
+    periods: u32,
+) -> Result<Vec<PeriodForecast>> {
+    let mut forecasts = Vec::new();
+    let mut salery_period = first;
+
+    for _ in 0..periods {
+        let earned = payslip_from_period(store, job, wage_and_bonus, &salery_period, EARNED)?;
+        let projected = payslip_from_period(store, job, wage_and_bonus, &salery_period, PROJECTED)?;
+        // the next salery period starts where this one ends
+        let next = salery_period_containing(wage_and_bonus.period(), salery_period.end().date(), 0);
+
+        forecasts.push(PeriodForecast {
+            salery_period,
+            earned: earned.salery(),
+            projected: projected.salery(),
+        });
+        salery_period = next;
+    }
+
+    Ok(forecasts)
+}
+
+/// Every shift is clipped to the salery period, so only the time worked inside the period is paid and counted.
+/// This way a shift crossing from one period into the next is split between them.
+/// The time worked outside the period only counts towards the overtime of its day and week
+pub fn payslip_from_shifts(
+    shifts: &[Shift],
+    wage_and_bonus: &WageAndBonuses,
+    salery_period: &SaleryPeriod,
+) -> Payslip {
+    let period = salery_period.interval();
+    let before = Interval::new(NaiveDateTime::MIN, period.start());
+    let after = Interval::new(period.end(), NaiveDateTime::MAX);
+
+    let mut paid = Vec::new();
+    let mut parts = Vec::new();
+    for shift in shifts {
+        let whole = shift.interval();
+
+        if let Some(inside) = whole.intersection(&period) {
+            let salery = salery_from_shift(wage_and_bonus, shift, &inside);
+            parts.push(WorkedPart {
+                start: inside.start(),
+                day: shift.start().date(),
+                worked: salery.worked(),
+                paid: true,
+            });
+            paid.push(salery);
+        }
+
+        for outside in [whole.intersection(&before), whole.intersection(&after)]
+            .into_iter()
+            .flatten()
+        {
+            parts.push(WorkedPart {
+                start: outside.start(),
+                day: shift.start().date(),
+                worked: salery_from_shift(wage_and_bonus, shift, &outside).worked(),
+                paid: false,
+            });
+        }
+    }
+
+    let overtime = overtime_from_parts(wage_and_bonus, parts);
+
+    Payslip {
+        shifts: paid,
+        overtime,
+    }
+}
+
+/// Pays the part of the shift that was worked, taking out the unpaid break the way wage_and_bonuses says.
+/// When only a part of the shift is in the salery period, only that part's share of the break is taken out
+fn salery_from_shift(
+    wage_and_bonus: &WageAndBonuses,
+    shift: &Shift,
+    worked: &Interval,
+) -> ShiftSalery {
+    let mut entries = salery_entries_from_shift(wage_and_bonus, worked);
+    let mut worked_duration = worked.duration();
+
+    let unpaid_break = !wage_and_bonus.breaks().paid() && shift.break_duration() > Duration::zero();
+    if unpaid_break {
+        let break_share = scale_duration(
+            shift.break_duration(),
+            ratio(worked.duration(), shift.interval().duration()),
+        )
+        .min(worked.duration());
+
+        match wage_and_bonus.breaks().deduction() {
+            BreakDeduction::Base => {
+                for entry in entries.iter_mut() {
+                    if entry.kind == EntryKind::Base {
+                        entry.duration -= break_share;
+                    }
+                }
+                worked_duration -= break_share;
+            }
+            BreakDeduction::Proportional => {
+                let factor = 1.0 - ratio(break_share, worked.duration());
+                for entry in entries.iter_mut() {
+                    entry.duration = scale_duration(entry.duration, factor);
+                }
+                worked_duration -= break_share;
             }
-            if shift2_end <= salery_period.end() {
-                salery_entries.extend(salery_entries_from_shift(
-                    &wage_and_bonus,
-                    shift2_start,
-                    shift2_end,
-                ));
+            BreakDeduction::AtTime => {
+                if let Some(on_break) = shift.break_interval().and_then(|b| b.intersection(worked))
+                {
+                    let before = Interval::new(worked.start(), on_break.start());
+                    let after = Interval::new(on_break.end(), worked.end());
+
+                    let mut pieces = salery_entries_from_shift(wage_and_bonus, &before);
+                    pieces.extend(salery_entries_from_shift(wage_and_bonus, &after));
+                    entries = merge_entries(pieces.iter());
+                    worked_duration = before.duration() + after.duration();
+                }
             }
+        }
+    }
+
+    ShiftSalery {
+        shift_start: shift.start(),
+        shift_end: shift.end(),
+        break_duration: if unpaid_break {
+            shift.break_duration()
         } else {
-            salery_entries.extend(salery_entries_from_shift(
-                &wage_and_bonus,
-                shift_start,
-                shift_end,
+            Duration::zero()
+        },
+        worked: worked_duration,
+        entries: entries
+            .into_iter()
+            .filter(|entry| entry.duration > Duration::zero() || entry.kind == EntryKind::Base)
+            .collect(),
+    }
+}
+
+fn ratio(part: Duration, whole: Duration) -> f64 {
+    if whole <= Duration::zero() {
+        return 0.0;
+    }
+    part.num_seconds() as f64 / whole.num_seconds() as f64
+}
+
+fn scale_duration(duration: Duration, factor: f64) -> Duration {
+    Duration::seconds((duration.num_seconds() as f64 * factor).round() as i64)
+}
+
+/// The time worked in a part of a shift, either inside the salery period or outside it.
+/// Parts outside the period are never paid, but count towards the hours of their day and week
+struct WorkedPart {
+    start: NaiveDateTime,
+    day: NaiveDate,
+    worked: Duration,
+    paid: bool,
+}
+
+/// Shifts count towards the day and the week they start in.
+/// Hours paid as daily overtime are left out of the weekly hours, and both are left out of the hours of the period.
+/// The hours over a threshold are the last ones worked, so a day or week split between two periods pays its overtime in the period it was worked in
+fn overtime_from_parts(
+    wage_and_bonus: &WageAndBonuses,
+    mut parts: Vec<WorkedPart>,
+) -> Vec<SaleryEntry> {
+    let overtime = wage_and_bonus.overtime();
+    let base_rate_on = |day: NaiveDate| wage_and_bonus.rates_on(day).base_rate();
+    let mut entries = Vec::new();
+    parts.sort_by_key(|part| (part.day, part.start));
+
+    let mut days: BTreeMap<NaiveDate, Vec<usize>> = BTreeMap::new();
+    let mut weeks: BTreeMap<IsoWeek, Vec<usize>> = BTreeMap::new();
+    for (index, part) in parts.iter().enumerate() {
+        days.entry(part.day).or_default().push(index);
+        weeks.entry(part.day.iso_week()).or_default().push(index);
+    }
+
+    if let Some(rule) = overtime.daily() {
+        for (day, group) in &days {
+            let (earlier, paid) = take_overtime(&mut parts, group, rule.threshold());
+            entries.extend(overtime_entries(
+                "daily overtime",
+                rule,
+                earlier,
+                paid,
+                base_rate_on(*day),
             ));
         }
     }
 
-    salery_entries
+    // the overtime of a week is paid at the rate of the first day worked in it
+    if let Some(rule) = overtime.weekly() {
+        for group in weeks.values() {
+            let first_day = parts[group[0]].day;
+            let (earlier, paid) = take_overtime(&mut parts, group, rule.threshold());
+            entries.extend(overtime_entries(
+                "weekly overtime",
+                rule,
+                earlier,
+                paid,
+                base_rate_on(first_day),
+            ));
+        }
+    }
+
+    // and the overtime of the period at the rate of the last day worked
+    let paid_parts: Vec<&WorkedPart> = parts.iter().filter(|part| part.paid).collect();
+    if let (Some(rule), Some(last_part)) = (overtime.period(), paid_parts.last()) {
+        let period: Duration = paid_parts.iter().map(|part| part.worked).sum();
+        let extra = (period - rule.threshold()).max(Duration::zero());
+        entries.extend(overtime_entries(
+            "period overtime",
+            rule,
+            Duration::zero(),
+            extra,
+            base_rate_on(last_part.day),
+        ));
+    }
+
+    merge_entries(entries.iter())
 }
 
-pub fn calculate_salery_from_period(
-    database: &Database,
-    wage_and_bonus: &WageAndBonuses,
-    salery_period: SaleryPeriod,
-) -> f64 {
-    salery_entries_from_period(database, wage_and_bonus, &salery_period)
-        .iter()
-        .map(|entry| entry.duration.num_minutes() as f64 * entry.bonus_pr_hour / 60.0)
-        .sum()
+/// Takes the hours over the threshold out of the parts, in the order they were worked.
+/// Returns the overtime worked outside the period before the first paid part, and the overtime of the paid parts
+fn take_overtime(
+    parts: &mut [WorkedPart],
+    group: &[usize],
+    threshold: Duration,
+) -> (Duration, Duration) {
+    let mut total = Duration::zero();
+    let mut earlier = Duration::zero();
+    let mut paid = Duration::zero();
+    let mut seen_paid = false;
+
+    for index in group {
+        let part = &mut parts[*index];
+        let over = (total + part.worked - total.max(threshold)).max(Duration::zero());
+        total += part.worked;
+        part.worked -= over;
+
+        if part.paid {
+            seen_paid = true;
+            paid += over;
+        } else if !seen_paid {
+            earlier += over;
+        }
+    }
+
+    (earlier, paid)
 }
 
-pub fn duration_worked(database: &Database, salery_period: &SaleryPeriod) -> Duration {
-    let query = format!(
-        "Select * from {} where shift_start <= :shift_end and shift_end > :shift_start",
-        database.table()
-    );
+/// Splits the overtime into the tiers of the rule, each paying the part of its multiplier above the base rate.
+/// Overtime worked earlier, in another period, has already used up the first tiers by as much
+fn overtime_entries(
+    name: &str,
+    rule: &OvertimeRule,
+    mut earlier: Duration,
+    mut overtime: Duration,
+    base_rate: f64,
+) -> Vec<SaleryEntry> {
+    let mut entries = Vec::new();
 
-    let mut duration = Duration::weeks(0);
+    for tier in rule.tiers() {
+        if overtime <= Duration::zero() {
+            break;
+        }
 
-    for row in database
-        .prepare(query)
-        .unwrap()
-        .into_iter()
-        .bind(
-            &[
-                (
-                    ":shift_start",
-                    format!("{}", salery_period.start()).as_str(),
-                ),
-                (":shift_end", format!("{}", salery_period.end()).as_str()), //"2023-11-21"
-            ][..],
-        ) // change this, so it is the current salery period
-        .expect("Couldn't prepare statement for gettign shifts when calculating salery")
-        .map(|row| row.unwrap())
-    {
-        let shift_start = parse_naivedatetime_from_str(row.read::<&str, _>("shift_start")).unwrap();
-        let shift_end = parse_naivedatetime_from_str(row.read::<&str, _>("shift_end")).unwrap();
-
-        duration = duration
-            .checked_add(&shift_end.signed_duration_since(shift_start))
-            .unwrap();
+        let hours = match tier.hours() {
+            Some(hours) if hours <= earlier => {
+                earlier -= hours;
+                continue;
+            }
+            Some(hours) => (hours - earlier).min(overtime),
+            None => overtime,
+        };
+        earlier = Duration::zero();
+        overtime -= hours;
+
+        entries.push(SaleryEntry::new(
+            EntryKind::Overtime,
+            format!("{} x{}", name, tier.multiplier()),
+            hours,
+            base_rate * (tier.multiplier() - 1.0),
+        ));
     }
 
-    duration
-    // salery_entries_from_period(database, wage_and_bonus, &salery_period)
-    //     .iter()
-    //     .map(|entry| entry.duration)
-    //     .sum()
+    entries
 }
 
-// this function can be made way prettier
-/// Checks if shift overlaps any "bonus periods" in wage_and_bonuses and returns the produces salery entries
+/// Checks if shift overlaps any "bonus periods" in wage_and_bonuses and returns the produces salery entries.
+/// The shift is paid by the rates in force on the day it starts
 fn salery_entries_from_shift(
     wage_and_bonus: &WageAndBonuses,
-    shift_start: NaiveDateTime,
-    shift_end: NaiveDateTime,
+    shift: &Interval,
 ) -> Vec<SaleryEntry> {
-    let total_shift_duration = calculate_shift_time(shift_start, shift_end);
+    let rates = wage_and_bonus.rates_on(shift.start().date());
+
+    let mut salery_entries = vec![SaleryEntry::new(
+        EntryKind::Base,
+        "base rate".to_string(),
+        shift.duration(),
+        rates.base_rate(),
+    )];
 
-    let mut salery_entries: Vec<SaleryEntry> = wage_and_bonus
-        .general_time_periods()
+    // day of the week bonuses without any days never apply
+    let day_of_week_rates = rates
+        .day_of_week_rates()
         .iter()
-        .filter_map(|bonus| {
-            let beginning = shift_start.time().max(bonus.start_time());
-            let ending = shift_end.time().min(bonus.end_time());
-
-            if ending > beginning {
-                return Some(SaleryEntry::new(
-                    ending.signed_duration_since(beginning),
-                    bonus.bonus_pr_hour(),
-                ));
+        .filter(|bonus| bonus.days().is_some());
+
+    for bonus in rates.general_time_periods().iter().chain(day_of_week_rates) {
+        let overlap = bonus_overlap(bonus, shift);
+
+        if overlap > Duration::zero() {
+            salery_entries.push(SaleryEntry::new(
+                EntryKind::Bonus,
+                bonus.description(),
+                overlap,
+                bonus.bonus_pr_hour(),
+            ));
+        }
+    }
+
+    let holiday_hours = holiday_overlap(wage_and_bonus, shift);
+    if holiday_hours > Duration::zero() {
+        for bonus in wage_and_bonus.holidays().rates() {
+            salery_entries.push(SaleryEntry::new(
+                EntryKind::Bonus,
+                bonus.description(),
+                holiday_hours,
+                bonus.bonus_pr_hour(rates.base_rate()),
+            ));
+        }
+    }
+
+    salery_entries
+}
+
+/// Finds how much of the shift is worked on holidays, which last from midnight to midnight
+fn holiday_overlap(wage_and_bonus: &WageAndBonuses, shift: &Interval) -> Duration {
+    let mut overlap = Duration::zero();
+    let mut day = shift.start().date();
+
+    while day <= shift.end().date() {
+        if wage_and_bonus.holidays().is_holiday(day) {
+            let whole_day = Interval::new(
+                day.and_time(NaiveTime::MIN),
+                (day + Duration::days(1)).and_time(NaiveTime::MIN),
+            );
+            if let Some(covered) = whole_day.intersection(shift) {
+                overlap += covered.duration();
             }
-            None
-        })
-        .collect();
+        }
+        day += Duration::days(1);
+    }
+
+    overlap
+}
+
+/// Finds how much of the shift is covered by the bonus.
+/// A bonus that runs past midnight belongs to the day it starts on,
+/// so the window starting the day before the shift is checked as well
+fn bonus_overlap(bonus: &Bonus, shift: &Interval) -> Duration {
+    let mut overlap = Duration::zero();
+    let mut day = shift.start().date() - Duration::days(1);
+
+    while day <= shift.end().date() {
+        if bonus.applies_on(day.weekday()) {
+            if let Some(covered) = bonus.window_on(day).intersection(shift) {
+                overlap += covered.duration();
+            }
+        }
+        day += Duration::days(1);
+    }
+
+    overlap
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{
+        memory_store::MemoryStore,
+        wage_bonuses::{Bonus, Period, RateChange},
+    };
+
+    fn datetime(day: u32, hour: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, min, 0)
+            .unwrap()
+    }
+
+    fn wage_and_bonuses() -> WageAndBonuses {
+        WageAndBonuses::new(
+            100.0,
+            Period::Month,
+            vec![Bonus::new(20.0, "18:00".into(), "23:59".into(), None).unwrap()],
+            vec![Bonus::new(
+                10.0,
+                "14:00".into(),
+                "23:59".into(),
+                Some(vec!["saturday".into()]),
+            )
+            .unwrap()],
+        )
+    }
 
-    salery_entries.extend(
-        wage_and_bonus
-            .day_of_week_rates()
+    #[test]
+    fn payslip_of_the_shifts_in_the_store() {
+        let mut store = MemoryStore::new();
+        for day in 2..=4 {
+            store
+                .add_shift(
+                    &Shift::new(datetime(day, 12, 0), datetime(day, 13, 0)),
+                    "bar",
+                )
+                .unwrap();
+        }
+        let mut planned = Shift::new(datetime(5, 12, 0), datetime(5, 13, 0));
+        planned.set_status(ShiftStatus::Planned);
+        store.add_shift(&planned, "bar").unwrap();
+        store
+            .add_shift(&Shift::new(datetime(5, 12, 0), datetime(5, 13, 0)), "shop")
+            .unwrap();
+        let october = SaleryPeriod::new(datetime(1, 0, 0), datetime(31, 0, 0));
+
+        let earned = payslip_from_period(
+            &store,
+            "bar",
+            &wage_and_bonuses(),
+            &october,
+            &[ShiftStatus::Worked],
+        )
+        .unwrap();
+        let projected = payslip_from_period(
+            &store,
+            "bar",
+            &wage_and_bonuses(),
+            &october,
+            &[ShiftStatus::Worked, ShiftStatus::Planned],
+        )
+        .unwrap();
+
+        assert_eq!(earned.shifts().len(), 3);
+        assert_eq!(earned.salery(), 300.0);
+        assert_eq!(projected.salery(), 400.0);
+    }
+
+    #[test]
+    fn forecast_runs_from_one_period_into_the_next() {
+        let mut store = MemoryStore::new();
+        store
+            .add_shift(&Shift::new(datetime(2, 12, 0), datetime(2, 13, 0)), "bar")
+            .unwrap();
+        let november_2nd = NaiveDate::from_ymd_opt(2023, 11, 2).unwrap();
+        let mut planned = Shift::new(
+            november_2nd.and_hms_opt(12, 0, 0).unwrap(),
+            november_2nd.and_hms_opt(14, 0, 0).unwrap(),
+        );
+        planned.set_status(ShiftStatus::Planned);
+        store.add_shift(&planned, "bar").unwrap();
+        let october = SaleryPeriod::new(
+            datetime(1, 0, 0),
+            NaiveDate::from_ymd_opt(2023, 11, 1)
+                .unwrap()
+                .and_time(NaiveTime::MIN),
+        );
+
+        let forecasts = forecast(&store, "bar", &wage_and_bonuses(), october, 2).unwrap();
+
+        assert_eq!(forecasts.len(), 2);
+        assert_eq!(forecasts[0].earned(), 100.0);
+        assert_eq!(forecasts[0].planned(), 0.0);
+        assert_eq!(
+            forecasts[1].salery_period().start().date(),
+            NaiveDate::from_ymd_opt(2023, 11, 1).unwrap()
+        );
+        assert_eq!(forecasts[1].earned(), 0.0);
+        assert_eq!(forecasts[1].projected(), 200.0);
+    }
+
+    #[test]
+    fn shift_is_itemized_per_bonus() {
+        // 2023-10-07 is a saturday
+        let entries = salery_entries_from_shift(
+            &wage_and_bonuses(),
+            &Interval::new(datetime(7, 16, 0), datetime(7, 20, 0)),
+        );
+
+        let lines: Vec<(&str, i64, f64)> = entries
             .iter()
-            .filter_map(|bonus| {
-                let shift_weekday = shift_start.weekday();
-
-                if let Some(bonus_weekday) = bonus.days() {
-                    let bonus_weekday: Vec<Weekday> = bonus_weekday
-                        .iter()
-                        .filter_map(|s| s.parse::<Weekday>().ok())
-                        .collect();
-
-                    if bonus_weekday.contains(&shift_weekday) {
-                        let beginning = shift_start.time().max(bonus.start_time());
-                        let ending = shift_end.time().min(bonus.end_time());
-
-                        if ending > beginning {
-                            return Some(SaleryEntry::new(
-                                ending.signed_duration_since(beginning),
-                                bonus.bonus_pr_hour(),
-                            ));
-                        }
-                    }
-                }
+            .map(|e| {
+                (
+                    e.description(),
+                    e.duration().num_minutes(),
+                    e.bonus_pr_hour(),
+                )
+            })
+            .collect();
 
-                None
+        assert_eq!(
+            lines,
+            vec![
+                ("base rate", 240, 100.0),
+                ("bonus 18:00-23:59", 120, 20.0),
+                ("saturday bonus 14:00-23:59", 240, 10.0),
+            ]
+        );
+        assert_eq!(entries.iter().map(|e| e.salery()).sum::<f64>(), 480.0);
+    }
+
+    #[test]
+    fn overnight_shift_is_paid_every_minute() {
+        let entries = salery_entries_from_shift(
+            &wage_and_bonuses(),
+            &Interval::new(datetime(2, 22, 0), datetime(3, 6, 0)),
+        );
+
+        assert_eq!(entries[0].kind(), EntryKind::Base);
+        assert_eq!(entries[0].duration(), Duration::hours(8));
+        assert_eq!(entries[0].salery(), 800.0);
+    }
+
+    #[test]
+    fn bonus_ending_at_midnight_covers_the_last_minute() {
+        let wage_and_bonuses = WageAndBonuses::new(
+            100.0,
+            Period::Month,
+            vec![Bonus::new(20.0, "18:00".into(), "24:00".into(), None).unwrap()],
+            vec![],
+        );
+
+        let entries = salery_entries_from_shift(
+            &wage_and_bonuses,
+            &Interval::new(datetime(2, 22, 0), datetime(3, 2, 0)),
+        );
+
+        assert_eq!(entries[1].duration(), Duration::hours(2));
+    }
+
+    #[test]
+    fn shift_spanning_several_days_gets_every_bonus_window() {
+        let wage_and_bonuses = WageAndBonuses::new(
+            100.0,
+            Period::Month,
+            vec![Bonus::new(30.0, "22:00".into(), "06:00".into(), None).unwrap()],
+            vec![],
+        );
+
+        // from monday 20:00 to thursday 02:00
+        let entries = salery_entries_from_shift(
+            &wage_and_bonuses,
+            &Interval::new(datetime(2, 20, 0), datetime(5, 2, 0)),
+        );
+
+        assert_eq!(entries[0].duration(), Duration::hours(54));
+        assert_eq!(entries[1].duration(), Duration::hours(8 + 8 + 4));
+    }
+
+    #[test]
+    fn merging_entries_sums_durations_of_the_same_line() {
+        let entries = [
+            SaleryEntry::new(
+                EntryKind::Base,
+                "base rate".into(),
+                Duration::hours(2),
+                100.0,
+            ),
+            SaleryEntry::new(EntryKind::Bonus, "bonus".into(), Duration::hours(1), 20.0),
+            SaleryEntry::new(
+                EntryKind::Base,
+                "base rate".into(),
+                Duration::hours(3),
+                100.0,
+            ),
+        ];
+
+        let merged = merge_entries(entries.iter());
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].duration(), Duration::hours(5));
+        assert_eq!(merged[1].duration(), Duration::hours(1));
+    }
+
+    #[test]
+    fn bonus_window_wraps_around_midnight() {
+        let wage_and_bonuses = WageAndBonuses::new(
+            100.0,
+            Period::Month,
+            vec![Bonus::new(30.0, "22:00".into(), "06:00".into(), None).unwrap()],
+            vec![],
+        );
+
+        let evening = salery_entries_from_shift(
+            &wage_and_bonuses,
+            &Interval::new(datetime(2, 20, 0), datetime(2, 23, 0)),
+        );
+        let morning = salery_entries_from_shift(
+            &wage_and_bonuses,
+            &Interval::new(datetime(3, 4, 0), datetime(3, 8, 0)),
+        );
+
+        assert_eq!(evening[1].duration(), Duration::hours(1));
+        assert_eq!(morning[1].duration(), Duration::hours(2));
+    }
+
+    #[test]
+    fn day_of_week_window_runs_into_the_next_day() {
+        // saturday night into sunday morning, 2023-10-07 is a saturday
+        let wage_and_bonuses = WageAndBonuses::new(
+            100.0,
+            Period::Month,
+            vec![],
+            vec![Bonus::new(
+                50.0,
+                "22:00".into(),
+                "06:00".into(),
+                Some(vec!["saturday".into()]),
+            )
+            .unwrap()],
+        );
+
+        let sunday_morning = salery_entries_from_shift(
+            &wage_and_bonuses,
+            &Interval::new(datetime(8, 0, 0), datetime(8, 7, 0)),
+        );
+        let monday_morning = salery_entries_from_shift(
+            &wage_and_bonuses,
+            &Interval::new(datetime(9, 0, 0), datetime(9, 7, 0)),
+        );
+
+        assert_eq!(sunday_morning[1].duration(), Duration::hours(6));
+        assert_eq!(monday_morning.len(), 1);
+    }
+
+    #[test]
+    fn shift_straddling_two_periods_is_split_between_them() {
+        let first = SaleryPeriod::new(datetime(1, 0, 0), datetime(8, 0, 0));
+        let second = SaleryPeriod::new(datetime(8, 0, 0), datetime(15, 0, 0));
+        let shifts = [
+            Shift::new(datetime(7, 20, 0), datetime(8, 4, 0)),
+            Shift::new(datetime(10, 8, 0), datetime(10, 16, 0)),
+        ];
+
+        let first = payslip_from_shifts(&shifts, &wage_and_bonuses(), &first);
+        let second = payslip_from_shifts(&shifts, &wage_and_bonuses(), &second);
+        let everything = payslip_from_shifts(
+            &shifts,
+            &wage_and_bonuses(),
+            &SaleryPeriod::new(datetime(1, 0, 0), datetime(15, 0, 0)),
+        );
+
+        assert_eq!(first.duration_worked(), Duration::hours(4));
+        assert_eq!(second.duration_worked(), Duration::hours(12));
+        assert_eq!(
+            first.duration_worked() + second.duration_worked(),
+            everything.duration_worked()
+        );
+        assert!((first.salery() + second.salery() - everything.salery()).abs() < 1e-9);
+    }
+
+    fn wage_and_bonuses_with_overtime(overtime: &str) -> WageAndBonuses {
+        serde_json::from_str(&format!(
+            r#"{{
+                "base_rate": 100.0,
+                "period": null,
+                "general_time_periods": [],
+                "day_of_week_rates": [],
+                "overtime": {}
+            }}"#,
+            overtime
+        ))
+        .unwrap()
+    }
+
+    fn overtime_lines(payslip: &Payslip) -> Vec<(&str, i64, f64)> {
+        payslip
+            .overtime()
+            .iter()
+            .map(|e| {
+                (
+                    e.description(),
+                    e.duration().num_minutes(),
+                    e.bonus_pr_hour(),
+                )
             })
-            .collect::<Vec<SaleryEntry>>(),
-    );
+            .collect()
+    }
 
-    salery_entries.push(SaleryEntry::new(
-        total_shift_duration,
-        wage_and_bonus.base_rate(),
-    ));
+    #[test]
+    fn daily_overtime_is_paid_in_tiers() {
+        let wage_and_bonuses = wage_and_bonuses_with_overtime(
+            r#"{"daily": {"threshold_hours": 7.4, "tiers": [
+                {"hours": 3, "multiplier": 1.5},
+                {"multiplier": 2.0}
+            ]}}"#,
+        );
+        let period = SaleryPeriod::new(datetime(1, 0, 0), datetime(31, 0, 0));
+        let shifts = [
+            Shift::new(datetime(2, 7, 0), datetime(2, 18, 0)),
+            Shift::new(datetime(3, 7, 0), datetime(3, 15, 0)),
+        ];
 
-    salery_entries
-}
+        let payslip = payslip_from_shifts(&shifts, &wage_and_bonuses, &period);
+
+        assert_eq!(
+            overtime_lines(&payslip),
+            vec![
+                ("daily overtime x1.5", 180 + 36, 50.0),
+                ("daily overtime x2", 36, 100.0)
+            ]
+        );
+        assert!((payslip.salery() - (1900.0 + 3.6 * 50.0 + 0.6 * 100.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weekly_overtime_leaves_out_hours_paid_as_daily_overtime() {
+        let wage_and_bonuses = wage_and_bonuses_with_overtime(
+            r#"{
+                "daily": {"threshold_hours": 8, "tiers": [{"multiplier": 1.5}]},
+                "weekly": {"threshold_hours": 37, "tiers": [{"multiplier": 1.5}]}
+            }"#,
+        );
+        let period = SaleryPeriod::new(datetime(1, 0, 0), datetime(31, 0, 0));
+        // monday the 2nd to friday the 6th, 9 hours a day
+        let shifts: Vec<Shift> = (2..=6)
+            .map(|day| Shift::new(datetime(day, 7, 0), datetime(day, 16, 0)))
+            .collect();
+
+        let payslip = payslip_from_shifts(&shifts, &wage_and_bonuses, &period);
+
+        assert_eq!(
+            overtime_lines(&payslip),
+            vec![
+                ("daily overtime x1.5", 5 * 60, 50.0),
+                ("weekly overtime x1.5", 3 * 60, 50.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn week_split_between_two_periods_pays_its_overtime_where_it_was_worked() {
+        let wage_and_bonuses = wage_and_bonuses_with_overtime(
+            r#"{"weekly": {"threshold_hours": 37, "tiers": [
+                {"hours": 2, "multiplier": 1.5},
+                {"multiplier": 2.0}
+            ]}}"#,
+        );
+        let mut store = MemoryStore::new();
+        // monday the 2nd to thursday the 5th, 9 hours a day, and 4 hours on friday the 6th
+        for day in 2..=5 {
+            store
+                .add_shift(
+                    &Shift::new(datetime(day, 7, 0), datetime(day, 16, 0)),
+                    "bar",
+                )
+                .unwrap();
+        }
+        store
+            .add_shift(&Shift::new(datetime(6, 6, 0), datetime(6, 10, 0)), "bar")
+            .unwrap();
+        let first = SaleryPeriod::new(datetime(1, 0, 0), datetime(6, 8, 0));
+        let second = SaleryPeriod::new(datetime(6, 8, 0), datetime(31, 0, 0));
+        let payslip = |period| {
+            payslip_from_period(
+                &store,
+                "bar",
+                &wage_and_bonuses,
+                period,
+                &[ShiftStatus::Worked],
+            )
+            .unwrap()
+        };
+
+        // 38 hours are worked by 8:00 on friday, and the last 2 hours are in the second period
+        assert_eq!(
+            overtime_lines(&payslip(&first)),
+            vec![("weekly overtime x1.5", 60, 50.0)]
+        );
+        assert_eq!(
+            overtime_lines(&payslip(&second)),
+            vec![
+                ("weekly overtime x1.5", 60, 50.0),
+                ("weekly overtime x2", 60, 100.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn holiday_bonus_applies_to_the_hours_on_the_holiday() {
+        let wage_and_bonuses: WageAndBonuses = serde_json::from_str(
+            r#"{
+                "base_rate": 100.0,
+                "period": null,
+                "general_time_periods": [],
+                "day_of_week_rates": [],
+                "holidays": {
+                    "danish": true,
+                    "extra_dates": ["12-24"],
+                    "rates": [{"multiplier": 2.0}, {"bonus_pr_hour": 15.0}]
+                }
+            }"#,
+        )
+        .unwrap();
+        let christmas_eve = NaiveDate::from_ymd_opt(2023, 12, 24).unwrap();
+        // from the 23rd at 20:00 to christmas day at 02:00
+        let shift = Interval::new(
+            (christmas_eve - Duration::days(1))
+                .and_hms_opt(20, 0, 0)
+                .unwrap(),
+            (christmas_eve + Duration::days(1))
+                .and_hms_opt(2, 0, 0)
+                .unwrap(),
+        );
+
+        let entries = salery_entries_from_shift(&wage_and_bonuses, &shift);
+        let lines: Vec<(&str, i64, f64)> = entries
+            .iter()
+            .map(|e| (e.description(), e.duration().num_hours(), e.bonus_pr_hour()))
+            .collect();
 
-// #[cfg(test)]
-// mod test {
-//     use chrono::NaiveDate;
+        assert_eq!(
+            lines,
+            vec![
+                ("base rate", 30, 100.0),
+                ("holiday x2", 26, 100.0),
+                ("holiday bonus", 26, 15.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn shifts_are_paid_by_the_rates_in_force_on_their_day() {
+        let mut wage_and_bonuses = wage_and_bonuses();
+        wage_and_bonuses.add_rate_change(RateChange::new(
+            NaiveDate::from_ymd_opt(2023, 10, 5).unwrap(),
+            120.0,
+        ));
+        let period = SaleryPeriod::new(datetime(1, 0, 0), datetime(31, 0, 0));
+        let shifts = [
+            Shift::new(datetime(4, 8, 0), datetime(4, 10, 0)),
+            Shift::new(datetime(5, 8, 0), datetime(5, 10, 0)),
+        ];
+
+        let payslip = payslip_from_shifts(&shifts, &wage_and_bonuses, &period);
+
+        assert_eq!(payslip.shifts()[0].salery(), 200.0);
+        assert_eq!(payslip.shifts()[1].salery(), 240.0);
+        assert_eq!(payslip.totals().len(), 2);
+    }
 
-//     use super::*;
-//     #[test]
-//     fn calculate() {
-//         std::fs::remove_file("test.db").ok();
-//         let db = Database::open_or_create_db("test.db", "test");
+    fn payslip_with_break(deduction: &str, break_start: Option<NaiveDateTime>) -> Payslip {
+        let wage_and_bonuses: WageAndBonuses = serde_json::from_str(&format!(
+            r#"{{
+                "base_rate": 100.0,
+                "period": null,
+                "general_time_periods": [{{"bonus_pr_hour": 20.0, "start": "18:00", "end": "24:00"}}],
+                "day_of_week_rates": [],
+                "breaks": {{"deduction": "{}"}}
+            }}"#,
+            deduction
+        ))
+        .unwrap();
+        let period = SaleryPeriod::new(datetime(1, 0, 0), datetime(31, 0, 0));
+        let mut shift = Shift::new(datetime(2, 16, 0), datetime(2, 22, 0));
+        shift.add_break(Duration::minutes(60), break_start);
 
-//         for day in 2..=4 {
-//             let date = NaiveDate::from_ymd_opt(2023, 10, day).unwrap();
-//             let time_start = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
-//             let time_stop = NaiveTime::from_hms_opt(13, 0, 0).unwrap();
-//             let shift_start = NaiveDateTime::new(date, time_start);
-//             let shift_end = NaiveDateTime::new(date, time_stop);
+        payslip_from_shifts(&[shift], &wage_and_bonuses, &period)
+    }
+
+    fn shift_lines(payslip: &Payslip) -> Vec<(&str, i64)> {
+        payslip.shifts()[0]
+            .entries()
+            .iter()
+            .map(|e| (e.description(), e.duration().num_minutes()))
+            .collect()
+    }
 
-//             db.add_shift(shift_start, shift_end, None).unwrap();
-//         }
+    #[test]
+    fn unpaid_break_is_taken_from_the_base_hours() {
+        let payslip = payslip_with_break("base", None);
 
-//         let wage_and_bonuses = WageAndBonuses::open("Wage_bonuses_map.json").unwrap();
-//         let salery = _calculate_salery(db, &wage_and_bonuses);
-//         assert!(salery > 410.0 && salery < 411.0);
-//     }
-// }
+        assert_eq!(payslip.duration_worked(), Duration::hours(5));
+        assert_eq!(
+            shift_lines(&payslip),
+            vec![("base rate", 300), ("bonus 18:00-24:00", 240)]
+        );
+    }
+
+    #[test]
+    fn unpaid_break_is_taken_proportionally_from_every_line() {
+        let payslip = payslip_with_break("proportional", None);
+
+        assert_eq!(payslip.duration_worked(), Duration::hours(5));
+        assert_eq!(
+            shift_lines(&payslip),
+            vec![("base rate", 300), ("bonus 18:00-24:00", 200)]
+        );
+    }
+
+    #[test]
+    fn unpaid_break_is_cut_out_at_the_time_it_was_taken() {
+        let payslip = payslip_with_break("at_time", Some(datetime(2, 20, 0)));
+
+        assert_eq!(payslip.duration_worked(), Duration::hours(5));
+        assert_eq!(
+            shift_lines(&payslip),
+            vec![("base rate", 300), ("bonus 18:00-24:00", 180)]
+        );
+    }
+}
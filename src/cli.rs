@@ -1,4 +1,8 @@
-use clap::{command, Parser, Subcommand};
+use std::str::FromStr;
+
+use clap::{Parser, Subcommand};
+
+use salery_calculator::{ExportFormat, ShiftStatus};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -6,12 +10,37 @@ pub struct Cli {
     ///What the program should do
     #[command(subcommand)]
     operation: Option<Operation>,
+    /// Where the shifts are kept: sqlite for DB.db, or json for shifts.json next to the program, which is easy to read and keep in git
+    #[arg(long, global = true, default_value = "sqlite")]
+    store: StoreKind,
 }
 
 impl Cli {
     pub fn operation(&self) -> Option<&Operation> {
         self.operation.as_ref()
     }
+
+    pub fn store(&self) -> StoreKind {
+        self.store
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum StoreKind {
+    Sqlite,
+    Json,
+}
+
+impl FromStr for StoreKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sqlite" => Ok(StoreKind::Sqlite),
+            "json" => Ok(StoreKind::Json),
+            _ => Err(format!("{} is not a store, use sqlite or json", s)),
+        }
+    }
 }
 
 #[derive(Subcommand, Clone, Debug)]
@@ -19,13 +48,31 @@ pub enum Operation {
     /// adds shift to database
     Add {
         /// YY-MM-DD hh:mm
-        start: String,
+        #[arg(required_unless_present = "template")]
+        start: Option<String>,
         /// YY-MM-DD hh:mm
-        end: String,
-        /// subtracts the break from the end of the shift
+        #[arg(required_unless_present = "template")]
+        end: Option<String>,
+        /// Add the shift from a template in the config instead of giving the start and end
+        #[arg(short, long, requires = "date", conflicts_with_all = ["start", "end"])]
+        template: Option<String>,
+        /// YYYY-MM-DD, the day the shift from the template starts on
+        #[arg(short, long, requires = "template")]
+        date: Option<String>,
         /// the break should be defined in whole minutes
+        /// it is stored along with the shift, and taken out of the pay if breaks are unpaid
+        /// leaving it out uses the break rules of the job, use 0 for a shift without a break
         #[arg(short = 'b', long = "break")]
         break_duration: Option<i64>,
+        /// When the break started, eg. "12:00". Without it the break is taken to be in the middle of the shift
+        #[arg(long = "break-start")]
+        break_start: Option<String>,
+        /// The job the shift was worked at, can be left out if you only have one job
+        #[arg(short, long)]
+        job: Option<String>,
+        /// planned, worked or cancelled
+        #[arg(long, default_value = "worked")]
+        status: ShiftStatus,
     },
     /// Removes shift from the database
     Remove { id: u32 },
@@ -39,15 +86,179 @@ pub enum Operation {
         sort: bool,
         #[arg(short, long)]
         offset: Option<u32>,
+        /// Only list the shifts of this job
+        #[arg(short, long)]
+        job: Option<String>,
     },
-    /// Calculates this months salery
+    /// Calculates this months salery, and what it will be when the planned shifts have been worked
     Calculate {
         /// calculate the salery period that is equal to the current "minus" the offset
         #[arg(short, long)]
         offset: Option<u32>,
+        /// Only calculate the salery of this job, otherwise every job is calculated along with the total
+        #[arg(short, long)]
+        job: Option<String>,
+    },
+    /// Projects the salery of the coming salery periods from the worked and planned shifts
+    Forecast {
+        /// How many salery periods to project, counting the current one
+        #[arg(short, long, default_value_t = 3)]
+        periods: u32,
+        /// Only project the salery of this job
+        #[arg(short, long)]
+        job: Option<String>,
+    },
+    /// Adds a job with its own wage, bonuses and salery period
+    AddJob {
+        /// The name used to pick the job with --job
+        name: String,
+    },
+    /// Sets a new base rate from the given day, keeping the rates used before that day
+    AddRate {
+        /// YYYY-MM-DD, the first day the new base rate is paid
+        effective_from: String,
+        /// The new hourly wage
+        base_rate: f64,
+        /// The job getting the new rate, can be left out if you only have one job
+        #[arg(short, long)]
+        job: Option<String>,
+    },
+    /// Shows the holiday pay earned in each salery period, and the running balance
+    HolidayPay {
+        /// How many salery periods to show, counting back from the current one
+        #[arg(short, long, default_value_t = 12)]
+        periods: u32,
+        /// Only show the holiday pay of this job
+        #[arg(short, long)]
+        job: Option<String>,
+    },
+    /// Adds the events in an iCalendar (.ics) file as shifts, eg. from a published rota
+    ImportIcs {
+        /// The .ics file
+        file: String,
+        /// Only add events with this in their summary, eg. the name of the job in the rota
+        #[arg(short, long)]
+        summary: Option<String>,
+        /// YYYY-MM-DD, leave out the events before this day
+        #[arg(long)]
+        from: Option<String>,
+        /// YYYY-MM-DD, leave out the events after this day
+        #[arg(long)]
+        to: Option<String>,
+        /// The job of the shifts, can be left out if you only have one job
+        #[arg(short, long)]
+        job: Option<String>,
+        /// planned, worked or cancelled. Events that are cancelled in the calendar are always cancelled
+        #[arg(long, default_value = "planned")]
+        status: ShiftStatus,
+        /// Show what would be added without adding anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Adds a shift from a template on the given days of every week in a range of dates
+    Schedule {
+        /// The name of the template in the config
+        template: String,
+        /// The days of the week written in english and seperated by a comma, eg. monday,wednesday
+        #[arg(long, value_delimiter = ',', required = true)]
+        days: Vec<String>,
+        /// YYYY-MM-DD, the first day of the schedule
+        #[arg(long)]
+        from: String,
+        /// YYYY-MM-DD, the last day of the schedule
+        #[arg(long)]
+        to: String,
+        /// The job the shifts are worked at, can be left out if you only have one job
+        #[arg(short, long)]
+        job: Option<String>,
+        /// Add the shifts without showing them and asking first
+        #[arg(short, long)]
+        yes: bool,
+        /// planned, worked or cancelled
+        #[arg(long, default_value = "planned")]
+        status: ShiftStatus,
+    },
+    /// Adds the shifts in a csv file, eg. exported from a spreadsheet
+    Import {
+        /// The csv file, where the first line names the columns
+        file: String,
+        /// The column with the start of the shift
+        #[arg(long, default_value = "start")]
+        start_column: String,
+        /// The column with the end of the shift
+        #[arg(long, default_value = "end")]
+        end_column: String,
+        /// A column with the date, when the start and end columns only have the time of day
+        #[arg(long)]
+        date_column: Option<String>,
+        /// A column with the break in whole minutes, rows without it get the break from the break rules
+        #[arg(long)]
+        break_column: Option<String>,
+        /// A column with the job of the shift. Rows without one get the job from --job, or are left out when you have more than one job and --job isn't given
+        #[arg(long)]
+        job_column: Option<String>,
+        /// A column with the status of the shift, planned, worked or cancelled
+        #[arg(long)]
+        status_column: Option<String>,
+        /// The character seperating the columns
+        #[arg(long, default_value_t = ',')]
+        delimiter: char,
+        /// A format the dates and times are written in, eg. "%d/%m/%Y %H.%M". Can be given more than once
+        #[arg(long)]
+        format: Vec<String>,
+        /// The job of the shifts, can be left out if you only have one job or use a job column
+        #[arg(short, long)]
+        job: Option<String>,
+        /// Show what would be added without adding anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Writes the shifts of this salery period as csv, json or ics, eg. to use them in a spreadsheet or a calendar
+    Export {
+        /// csv, json or ics. The events of ics files always have the estimated pay in their description
+        #[arg(short, long, default_value = "csv")]
+        format: ExportFormat,
+        /// Export every shift ever
+        #[arg(short, long)]
+        all: bool,
+        /// export the salery period that is equal to the current "minus" the offset
+        #[arg(short, long)]
+        offset: Option<u32>,
+        /// Only export the shifts of this job
+        #[arg(short, long)]
+        job: Option<String>,
+        /// Add the pay of every shift and what it is made up of
+        #[arg(short, long)]
+        pay: bool,
+        /// The file to write to, otherwise the shifts are written in the terminal
+        #[arg(long)]
+        output: Option<String>,
     },
+    /// Starts a shift now, which is ended with clock-out
+    ClockIn {
+        /// The job the shift is worked at, can be left out if you only have one job
+        #[arg(short, long)]
+        job: Option<String>,
+    },
+    /// Ends the shift started with clock-in now
+    ClockOut {
+        /// the break should be defined in whole minutes
+        /// leaving it out uses the break rules of the job, use 0 for a shift without a break
+        #[arg(short = 'b', long = "break")]
+        break_duration: Option<i64>,
+        /// When the break started, eg. "12:00". Without it the break is taken to be in the middle of the shift
+        #[arg(long = "break-start")]
+        break_start: Option<String>,
+    },
+    /// Shows the shift you are clocked in on, and what you have earned on it so far
+    Status,
     /// Deletes the database
     DropDatabase,
+    /// Manage the version of the sqlite database
+    Db {
+        #[command(subcommand)]
+        operation: DbOperation,
+    },
     /// Edit a shift choosen from it's id, takes atleast one other argument
     EditShift {
         /// Shift id in the database, call "list" to see shifts in the database
@@ -58,5 +269,24 @@ pub enum Operation {
         /// change the shift's end to this
         #[arg(short, long)]
         end: Option<String>,
+        /// change the shift's break to this many minutes
+        #[arg(short = 'b', long = "break")]
+        break_duration: Option<i64>,
+        /// change when the shift's break started, written with the date like start and end
+        #[arg(long = "break-start")]
+        break_start: Option<String>,
+        /// change the shift's status to planned, worked or cancelled
+        #[arg(long)]
+        status: Option<ShiftStatus>,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum DbOperation {
+    /// Upgrades the database to the newest version. This also happens when any other command is run
+    Migrate {
+        /// Only show the version of the database and which migrations have been run
+        #[arg(long)]
+        status: bool,
     },
 }
@@ -1,132 +1,488 @@
-use crate::time::SQLformat;
-use std::path::Path;
+use crate::{
+    error::{Error, Result},
+    migrations::{latest_version, version_from_columns, Migration, Upgrade, MIGRATIONS},
+    shift::Shift,
+    store::{check_shift, no_shift, stored_id, ShiftStore},
+    time::{parse_naivedatetime_from_str, SQLformat, SaleryPeriod},
+};
+use std::path::{Path, PathBuf};
 
 use chrono::{Duration, NaiveDateTime};
-use sqlite::{self, Connection, Error, Statement};
+use sqlite::{self, Connection, Row, State, Value};
 
+/// The shifts of every job, stored in a table of a sqlite database
 pub struct Database {
     connection: Connection,
     table: String,
+    path: PathBuf,
 }
 
 impl Database {
-    pub fn open_or_create_db<P: AsRef<Path>>(path: P, table_name: &str) -> Self {
-        let db = Connection::open(path).expect("Couldnt open/create connection");
-
-        let query = format!(
-            "create table if not exists {}(
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                shift_start DATETIME NOT NULL,
-                shift_end DATETIME NOT NULL
-            )",
-            table_name
-        );
+    /// Opens the database and upgrades the table to the newest version
+    pub fn open_or_create_db<P: AsRef<Path>>(path: P, table_name: &str) -> Result<Self> {
+        let database = Database::open(path, table_name)?;
+        database.migrate()?;
 
-        db.execute(query).expect("couldnt execute statement");
+        Ok(database)
+    }
+
+    /// Opens the database as it is, without upgrading the table
+    pub fn open<P: AsRef<Path>>(path: P, table_name: &str) -> Result<Self> {
+        if !is_identifier(table_name) {
+            return Err(Error::Database(format!(
+                "{} can't be used as the name of a table",
+                table_name
+            )));
+        }
 
-        Database {
+        let db = Connection::open(&path).map_err(|err| {
+            Error::Database(format!(
+                "couldn't open {}: {}",
+                path.as_ref().display(),
+                err
+            ))
+        })?;
+
+        Ok(Database {
             connection: db,
             table: table_name.to_owned(),
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    /// The version of the table, ie. the last migration that has been run on it. Reading it never writes to the database
+    pub fn schema_version(&self) -> Result<i64> {
+        let has_versions = !self
+            .query(
+                "select name from sqlite_master where type = 'table' and name = 'schema_version'"
+                    .to_string(),
+                &[],
+            )?
+            .is_empty();
+
+        if has_versions {
+            let stored = self
+                .query(
+                    "select version from schema_version where table_name = :table".to_string(),
+                    &[(":table", self.table.as_str().into())],
+                )?
+                .first()
+                .map(|row| row.read::<i64, _>("version"));
+
+            if let Some(version) = stored {
+                return Ok(version);
+            }
         }
+
+        let columns: Vec<(String, bool)> = self
+            .query(format!("pragma table_info({})", self.table), &[])?
+            .iter()
+            .map(|row| {
+                (
+                    row.read::<&str, _>("name").to_string(),
+                    row.read::<i64, _>("notnull") == 1,
+                )
+            })
+            .collect();
+
+        Ok(version_from_columns(&columns))
     }
 
-    pub fn drop_table(&self) {
-        let query = format!("drop table if exists {}", self.table);
+    /// Every migration, along with whether the table has had it
+    pub fn migrations(&self) -> Result<Vec<(&'static Migration, bool)>> {
+        let version = self.schema_version()?;
 
-        self.connection.execute(query).unwrap();
+        Ok(MIGRATIONS
+            .iter()
+            .map(|migration| (migration, migration.version() <= version))
+            .collect())
     }
 
-    pub fn edit_shift(
-        &self,
-        shift_id: u32,
-        start: &Option<NaiveDateTime>,
-        end: &Option<NaiveDateTime>,
-    ) -> Result<(), Error> {
-        if let (Some(s), Some(e)) = (&start, &end) {
-            if s > e {
-                panic!("The end of the shift should be after the start");
-            }
-        } else if start.is_none() && end.is_none() {
-            panic!("Edit the start and/or the end of the shift");
+    /// Runs the migrations the table is missing, each along with storing the new version so a failing migration is rolled back.
+    /// A copy of the database file is taken first, unless the table is new
+    pub fn migrate(&self) -> Result<Upgrade> {
+        let version = self.schema_version()?;
+        let pending: Vec<&Migration> = MIGRATIONS
+            .iter()
+            .filter(|migration| migration.version() > version)
+            .collect();
+
+        if pending.is_empty() {
+            return Ok(Upgrade::new(version, version, None, pending));
         }
 
-        let mut query = format!("update {} set", self.table);
+        let backup = if version > 0 && self.path.is_file() {
+            let backup = self.backup_path(version);
+            std::fs::copy(&self.path, &backup).map_err(|err| {
+                Error::Database(format!(
+                    "couldn't take a backup of the database before upgrading it: {}",
+                    err
+                ))
+            })?;
+            Some(backup)
+        } else {
+            None
+        };
+
+        self.connection.execute(
+            "create table if not exists schema_version(
+                table_name TEXT PRIMARY KEY,
+                version INTEGER NOT NULL
+            )",
+        )?;
 
-        match start {
-            Some(ndt) => query = format!("{} shift_start = {:#?},", query, ndt.sql_format()),
-            None => {}
+        for migration in &pending {
+            if let Err(err) = self.run_migration(migration) {
+                // a failed statement leaves the transaction open
+                let _ = self.connection.execute("rollback");
+                return Err(Error::Database(format!(
+                    "couldn't upgrade the database to version {}: {}",
+                    migration.version(),
+                    err
+                )));
+            }
         }
 
-        match end {
-            Some(ndt) => query = format!("{} shift_end = {:#?},", query, ndt.sql_format()),
-            None => {}
+        Ok(Upgrade::new(version, latest_version(), backup, pending))
+    }
+
+    /// Runs the statements of the migration and stores the new version in a single transaction
+    fn run_migration(&self, migration: &Migration) -> sqlite::Result<()> {
+        self.connection
+            .execute(format!("begin; {}", migration.statements(&self.table)))?;
+
+        let mut statement = self.connection.prepare(
+            "insert or replace into schema_version (table_name, version) values (:table, :version)",
+        )?;
+        statement.bind::<&[(_, Value)]>(&[
+            (":table", self.table.as_str().into()),
+            (":version", migration.version().into()),
+        ])?;
+        while statement.next()? != State::Done {}
+
+        self.connection.execute("commit")
+    }
+
+    /// Next to the database, eg. DB.db.v3.backup
+    fn backup_path(&self, version: i64) -> PathBuf {
+        let mut file_name = self.path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(format!(".v{}.backup", version));
+
+        self.path.with_file_name(file_name)
+    }
+
+    /// Whether there is a shift with the id, including a shift that is clocked in
+    fn has_id(&self, shift_id: i64) -> Result<bool> {
+        Ok(!self
+            .query(
+                format!("select id from {} where id = :id", self.table),
+                &[(":id", shift_id.into())],
+            )?
+            .is_empty())
+    }
+
+    fn execute(&self, statement: String, bindings: &[(&str, Value)]) -> Result<()> {
+        let mut statement = self.connection.prepare(statement)?;
+        statement.bind(bindings)?;
+        while statement.next()? != State::Done {}
+
+        Ok(())
+    }
+
+    fn query(&self, statement: String, bindings: &[(&str, Value)]) -> Result<Vec<Row>> {
+        let rows = self
+            .connection
+            .prepare(statement)?
+            .into_iter()
+            .bind(bindings)?
+            .collect::<std::result::Result<Vec<Row>, sqlite::Error>>()?;
+
+        Ok(rows)
+    }
+}
+
+impl ShiftStore for Database {
+    fn remove_shift(&mut self, shift_id: i64) -> Result<()> {
+        if !self.has_id(shift_id)? {
+            return Err(no_shift(shift_id));
         }
 
-        // remove the trailing comma
-        query.pop();
+        self.execute(
+            format!("delete from {} where id = :id", self.table),
+            &[(":id", shift_id.into())],
+        )
+    }
 
-        query = format!("{} where id = {}", query, shift_id);
+    fn add_shift(&mut self, shift: &Shift, job: &str) -> Result<()> {
+        check_shift(shift)?;
 
-        self.connection.execute(query)
+        self.execute(
+            format!(
+                "INSERT into {} (shift_start, shift_end, job, break_minutes, break_start, status) VALUES (:start, :end, :job, :break_minutes, :break_start, :status)",
+                self.table
+            ),
+            &[
+                (":start", shift.start().sql_format().into()),
+                (":end", shift.end().sql_format().into()),
+                (":job", job.into()),
+                (":break_minutes", shift.break_duration().num_minutes().into()),
+                (":break_start", shift.break_start().map(|ndt| ndt.sql_format()).into()),
+                (":status", shift.status().as_str().into()),
+            ],
+        )
     }
 
-    pub fn remove_shift(&self, shift_id: u32) -> Result<(), Error> {
-        self.connection.execute(format!(
-            "delete from {} where id = {}",
-            self.table, shift_id
-        ))
+    /// The shifts are added in a single transaction
+    fn add_shifts(&mut self, shifts: &[Shift], job: &str) -> Result<()> {
+        self.connection.execute("begin")?;
+
+        for shift in shifts {
+            if let Err(err) = self.add_shift(shift, job) {
+                self.connection.execute("rollback")?;
+                return Err(err);
+            }
+        }
+
+        Ok(self.connection.execute("commit")?)
     }
 
-    pub fn add_shift(
-        &self,
-        start: NaiveDateTime,
-        mut end: NaiveDateTime,
-        break_duration: Option<i64>,
-    ) -> Result<(), Error> {
-        if start > end {
-            panic!("the end of the shift should be after the start");
+    fn update_shift(&mut self, shift: &Shift) -> Result<()> {
+        check_shift(shift)?;
+        let shift_id = stored_id(shift)?;
+
+        self.execute(
+            format!(
+                "update {} set shift_start = :start, shift_end = :end, break_minutes = :break_minutes, break_start = :break_start, status = :status where id = :id",
+                self.table
+            ),
+            &[
+                (":start", shift.start().sql_format().into()),
+                (":end", shift.end().sql_format().into()),
+                (":break_minutes", shift.break_duration().num_minutes().into()),
+                (":break_start", shift.break_start().map(|ndt| ndt.sql_format()).into()),
+                (":status", shift.status().as_str().into()),
+                (":id", shift_id.into()),
+            ],
+        )?;
+
+        if self.connection.change_count() == 0 {
+            return Err(no_shift(shift_id));
         }
+        Ok(())
+    }
 
-        if let Some(dur) = break_duration {
-            end = end
-                .checked_sub_signed(Duration::minutes(dur))
-                .expect("break is out of range");
+    fn shifts_in_range(
+        &self,
+        job: &str,
+        salery_period: Option<&SaleryPeriod>,
+    ) -> Result<Vec<Shift>> {
+        let mut bindings: Vec<(&str, Value)> = vec![(":job", job.into())];
+        if let Some(salery_period) = salery_period {
+            bindings.push((":period_start", salery_period.start().sql_format().into()));
+            bindings.push((":period_end", salery_period.end().sql_format().into()));
         }
 
-        self.connection.execute(format!(
-            "INSERT into {} (shift_start, shift_end) VALUES ({:#?}, {:#?})",
-            self.table,
-            start.sql_format(),
-            end.sql_format()
-        ))
+        self.query(
+            format!(
+                "select * from {} where job = :job and shift_end is not null {} order by shift_start",
+                self.table,
+                if salery_period.is_some() {
+                    "and shift_start < :period_end and shift_end > :period_start"
+                } else {
+                    ""
+                }
+            ),
+            &bindings,
+        )?
+        .iter()
+        .map(shift_from_row)
+        .collect()
     }
 
-    pub fn table(&self) -> &str {
-        &self.table
+    fn shift_by_id(&self, shift_id: i64) -> Result<Option<Shift>> {
+        self.query(
+            format!(
+                "select * from {} where id = :id and shift_end is not null",
+                self.table
+            ),
+            &[(":id", shift_id.into())],
+        )?
+        .first()
+        .map(shift_from_row)
+        .transpose()
     }
 
-    pub fn prepare<T: AsRef<str>>(&self, statement: T) -> sqlite::Result<Statement<'_>> {
-        self.connection.prepare(statement)
+    // looked up in the table instead of reading every shift of the job
+    fn has_shift(&self, job: &str, start: NaiveDateTime, end: NaiveDateTime) -> Result<bool> {
+        Ok(!self
+            .query(
+                format!(
+                    "select id from {} where job = :job and shift_start = :start and shift_end = :end",
+                    self.table
+                ),
+                &[
+                    (":job", job.into()),
+                    (":start", start.sql_format().into()),
+                    (":end", end.sql_format().into()),
+                ],
+            )?
+            .is_empty())
     }
+
+    fn clock_in(&mut self, start: NaiveDateTime, job: &str) -> Result<()> {
+        self.execute(
+            format!(
+                "INSERT into {} (shift_start, shift_end, job) VALUES (:start, NULL, :job)",
+                self.table
+            ),
+            &[(":start", start.sql_format().into()), (":job", job.into())],
+        )
+    }
+
+    fn open_shift(&self) -> Result<Option<(i64, String, NaiveDateTime)>> {
+        self.query(
+            format!(
+                "select id, job, shift_start from {} where shift_end is null order by shift_start limit 1",
+                self.table
+            ),
+            &[],
+        )?
+        .first()
+        .map(|row| {
+            Ok((
+                row.read::<i64, _>("id"),
+                row.read::<&str, _>("job").to_string(),
+                read_date_time(row, "shift_start")?,
+            ))
+        })
+        .transpose()
+    }
+
+    fn remove_all(&mut self) -> Result<()> {
+        self.execute(format!("delete from {}", self.table), &[])
+    }
+}
+
+fn shift_from_row(row: &Row) -> Result<Shift> {
+    let mut shift = Shift::new(
+        read_date_time(row, "shift_start")?,
+        read_date_time(row, "shift_end")?,
+    );
+    shift.set_id(row.read::<i64, _>("id"));
+    let break_start = match row.read::<Option<&str>, _>("break_start") {
+        Some(_) => Some(read_date_time(row, "break_start")?),
+        None => None,
+    };
+    shift.add_break(
+        Duration::minutes(row.read::<i64, _>("break_minutes")),
+        break_start,
+    );
+    shift.set_status(
+        row.read::<&str, _>("status")
+            .parse()
+            .map_err(Error::Database)?,
+    );
+    Ok(shift)
+}
+
+/// A time stored in the database that can't be read means the database has been changed by something else
+fn read_date_time(row: &Row, column: &str) -> Result<NaiveDateTime> {
+    let value = row.read::<&str, _>(column);
+
+    parse_naivedatetime_from_str(value).map_err(|_| {
+        Error::Database(format!(
+            "the {} \"{}\" of the shift with the id {} can't be read",
+            column,
+            value,
+            row.read::<i64, _>("id")
+        ))
+    })
+}
+
+/// Table names can't be bound like values, so only plain names of letters, digits and underscores are used
+fn is_identifier(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::shift::ShiftStatus;
 
-    // this test needs to be redisigned, along with database struct
+    fn datetime(day: u32, hour: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2023, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn new_table_is_created_at_the_latest_version() {
+        let mut db = Database::open_or_create_db(":memory:", "shifts").unwrap();
+
+        assert_eq!(db.schema_version().unwrap(), latest_version());
+        assert!(db.migrate().unwrap().applied().is_empty());
+
+        db.add_shift(&Shift::new(datetime(2, 8), datetime(2, 16)), "bar")
+            .unwrap();
+        assert_eq!(db.shifts_in_range("bar", None).unwrap().len(), 1);
+    }
 
-    // #[test]
-    // fn insert_into_db() {
-    //     let db = Database::open_or_create_db("/DB.db");
+    #[test]
+    fn table_from_before_versions_is_upgraded_keeping_its_shifts() {
+        let mut db = Database::open(":memory:", "shifts").unwrap();
+        db.connection
+            .execute(
+                "create table shifts(
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    shift_start DATETIME NOT NULL,
+                    shift_end DATETIME NOT NULL
+                );
+                insert into shifts (shift_start, shift_end) values ('2023-10-02 08:00:00', '2023-10-02 16:00:00');",
+            )
+            .unwrap();
+        assert_eq!(db.schema_version().unwrap(), 1);
+        // reading the version doesn't write to the database
+        assert!(db
+            .query(
+                "select name from sqlite_master where name = 'schema_version'".to_string(),
+                &[],
+            )
+            .unwrap()
+            .is_empty());
 
-    //     let date = NaiveDate::from_ymd_opt(2023, 10, 30).unwrap();
-    //     let time = NaiveTime::from_hms_opt(23, 0, 0).unwrap();
-    //     let start = NaiveDateTime::new(date, time);
+        let upgrade = db.migrate().unwrap();
 
-    //     let date = NaiveDate::from_ymd_opt(2023, 10, 31).unwrap();
-    //     let time = NaiveTime::from_hms_opt(3, 0, 0).unwrap();
-    //     let end = NaiveDateTime::new(date, time);
+        assert_eq!(upgrade.applied().len() as i64, latest_version() - 1);
+        assert_eq!((upgrade.from(), upgrade.to()), (1, latest_version()));
+        // a database in memory has no file to copy
+        assert!(upgrade.backup().is_none());
+        assert_eq!(db.schema_version().unwrap(), latest_version());
+        let shifts = db
+            .shifts_in_range(crate::wage_bonuses::DEFAULT_JOB, None)
+            .unwrap();
+        assert_eq!(shifts.len(), 1);
+        assert_eq!(shifts[0].status(), ShiftStatus::Worked);
+        db.clock_in(datetime(3, 8), "bar").unwrap();
+        assert_eq!(
+            db.open_shift().unwrap().map(|(_, _, start)| start),
+            Some(datetime(3, 8))
+        );
+    }
 
-    //     db.add_shift(start, end).unwrap();
-    // }
+    #[test]
+    fn only_plain_names_are_used_for_tables() {
+        assert!(is_identifier("shifts"));
+        assert!(is_identifier("_shifts_2"));
+        assert!(!is_identifier(""));
+        assert!(!is_identifier("2shifts"));
+        assert!(!is_identifier("shifts; drop table shifts"));
+        assert!(matches!(
+            Database::open(":memory:", "shifts; drop table shifts"),
+            Err(Error::Database(_))
+        ));
+    }
 }
@@ -0,0 +1,73 @@
+use std::fmt;
+
+/// Everything that can stop a command. Every kind has its own exit code, so scripts running the program can tell them apart
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The config file couldn't be read or written, or holds something that doesn't make sense
+    Config(String),
+    /// Input that couldn't be read, eg. a date in an unknown format
+    Parse(String),
+    /// The database couldn't be opened, read or written
+    Database(String),
+    /// Input that was read, but can't be used, eg. a shift ending before it starts
+    Validation(String),
+    /// A file given on the command line couldn't be read or written
+    Io(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// 1 is left for panics and 2 for wrong arguments, which clap exits with
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Config(_) => 3,
+            Self::Parse(_) => 4,
+            Self::Database(_) => 5,
+            Self::Validation(_) => 6,
+            Self::Io(_) => 7,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(message) => write!(f, "config error: {}", message),
+            Self::Parse(message) => write!(f, "{}", message),
+            Self::Database(message) => write!(f, "database error: {}", message),
+            Self::Validation(message) => write!(f, "{}", message),
+            Self::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<sqlite::Error> for Error {
+    fn from(err: sqlite::Error) -> Self {
+        Self::Database(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_kind_of_error_has_its_own_exit_code() {
+        let errors = [
+            Error::Config(String::new()),
+            Error::Parse(String::new()),
+            Error::Database(String::new()),
+            Error::Validation(String::new()),
+            Error::Io(String::new()),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(Error::exit_code).collect();
+        codes.sort();
+        codes.dedup();
+
+        assert_eq!(codes.len(), errors.len());
+        assert!(codes.iter().all(|code| *code > 2));
+    }
+}
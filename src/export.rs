@@ -0,0 +1,340 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDateTime, Utc};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    calculate::payslip_from_shifts,
+    error::{Error, Result},
+    ics::to_ics,
+    shift::{Shift, ShiftStatus},
+    store::{stored_id, ShiftStore},
+    time::{SQLformat, SaleryPeriod},
+    wage_bonuses::WageAndBonuses,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Ics,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "ics" => Ok(ExportFormat::Ics),
+            _ => Err(format!("{} is not a format, use csv, json or ics", s)),
+        }
+    }
+}
+
+/// A shift as it is written to the exported file
+#[derive(Serialize)]
+pub struct ExportedShift {
+    id: i64,
+    job: String,
+    #[serde(serialize_with = "serialize_date_time")]
+    start: NaiveDateTime,
+    #[serde(serialize_with = "serialize_date_time")]
+    end: NaiveDateTime,
+    #[serde(rename = "break")]
+    break_minutes: i64,
+    #[serde(serialize_with = "serialize_status")]
+    status: ShiftStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pay: Option<ExportedPay>,
+}
+
+/// The pay of a single shift. Overtime is left out, since it is paid for the whole day, week or salery period
+#[derive(Serialize)]
+pub struct ExportedPay {
+    worked_hours: f64,
+    total: f64,
+    entries: Vec<ExportedEntry>,
+}
+
+#[derive(Serialize)]
+pub struct ExportedEntry {
+    description: String,
+    hours: f64,
+    rate: f64,
+    amount: f64,
+}
+
+impl ExportedShift {
+    /// With a salery period only the part of the shift inside it is paid, like when calculating the salery.
+    /// Cancelled shifts are never paid
+    pub fn new(
+        job: &str,
+        shift: &Shift,
+        wage_and_bonuses: &WageAndBonuses,
+        salery_period: Option<&SaleryPeriod>,
+        with_pay: bool,
+    ) -> Result<Self> {
+        let id = stored_id(shift)?;
+        let pay = with_pay.then(|| {
+            let whole_shift = SaleryPeriod::new(shift.start(), shift.end());
+            let payslip = payslip_from_shifts(
+                std::slice::from_ref(shift),
+                wage_and_bonuses,
+                salery_period.unwrap_or(&whole_shift),
+            );
+            let paid = payslip
+                .shifts()
+                .first()
+                .filter(|_| shift.status() != ShiftStatus::Cancelled);
+
+            ExportedPay {
+                worked_hours: paid.map_or(0.0, |paid| hours(paid.worked().num_seconds())),
+                total: paid.map_or(0.0, |paid| round(paid.salery())),
+                entries: paid.map_or(Vec::new(), |paid| {
+                    paid.entries()
+                        .iter()
+                        .map(|entry| ExportedEntry {
+                            description: entry.description().to_string(),
+                            hours: hours(entry.duration().num_seconds()),
+                            rate: entry.bonus_pr_hour(),
+                            amount: round(entry.salery()),
+                        })
+                        .collect()
+                }),
+            }
+        });
+
+        Ok(Self {
+            id,
+            job: job.to_string(),
+            start: shift.start(),
+            end: shift.end(),
+            break_minutes: shift.break_duration().num_minutes(),
+            status: shift.status(),
+            pay,
+        })
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    pub fn job(&self) -> &str {
+        &self.job
+    }
+
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    pub fn end(&self) -> NaiveDateTime {
+        self.end
+    }
+
+    pub fn break_minutes(&self) -> i64 {
+        self.break_minutes
+    }
+
+    pub fn status(&self) -> ShiftStatus {
+        self.status
+    }
+
+    pub fn pay(&self) -> Option<&ExportedPay> {
+        self.pay.as_ref()
+    }
+
+    fn entries(&self) -> &[ExportedEntry] {
+        self.pay.as_ref().map_or(&[], |pay| &pay.entries)
+    }
+}
+
+impl ExportedPay {
+    pub fn worked_hours(&self) -> f64 {
+        self.worked_hours
+    }
+
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+}
+
+/// The shifts of the job in the salery period, or every shift of the job without one
+pub fn exported_shifts(
+    store: &dyn ShiftStore,
+    job: &str,
+    wage_and_bonuses: &WageAndBonuses,
+    salery_period: Option<&SaleryPeriod>,
+    with_pay: bool,
+) -> Result<Vec<ExportedShift>> {
+    store
+        .shifts_in_range(job, salery_period)?
+        .iter()
+        .map(|shift| ExportedShift::new(job, shift, wage_and_bonuses, salery_period, with_pay))
+        .collect()
+}
+
+pub fn export(shifts: &[ExportedShift], format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(shifts)
+            .map(|json| json + "\n")
+            .map_err(|err| Error::Io(format!("couldn't write the shifts as json: {}", err))),
+        ExportFormat::Csv => Ok(to_csv(shifts)),
+        ExportFormat::Ics => Ok(to_ics(shifts, Utc::now().naive_utc())),
+    }
+}
+
+fn serialize_date_time<S: Serializer>(
+    date_time: &NaiveDateTime,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&date_time.sql_format())
+}
+
+fn serialize_status<S: Serializer>(
+    status: &ShiftStatus,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(status.as_str())
+}
+
+/// The columns can be read by import. With the pay, every line of the payslip gets a column with its hours and one with its amount
+fn to_csv(shifts: &[ExportedShift]) -> String {
+    let mut descriptions: Vec<&str> = Vec::new();
+    for entry in shifts.iter().flat_map(|shift| shift.entries()) {
+        if !descriptions.contains(&entry.description.as_str()) {
+            descriptions.push(&entry.description);
+        }
+    }
+    let with_pay = shifts.iter().any(|shift| shift.pay.is_some());
+
+    let mut header: Vec<String> = ["id", "job", "start", "end", "break", "status"]
+        .iter()
+        .map(|column| column.to_string())
+        .collect();
+    if with_pay {
+        header.push("worked_hours".to_string());
+        header.push("pay".to_string());
+        for description in &descriptions {
+            header.push(format!("{} hours", description));
+            header.push(format!("{} kr.", description));
+        }
+    }
+
+    let mut lines = vec![csv_line(&header)];
+    for shift in shifts {
+        let mut fields = vec![
+            shift.id.to_string(),
+            shift.job.clone(),
+            shift.start.sql_format(),
+            shift.end.sql_format(),
+            shift.break_minutes.to_string(),
+            shift.status.to_string(),
+        ];
+        if let Some(pay) = &shift.pay {
+            fields.push(pay.worked_hours.to_string());
+            fields.push(format!("{:.2}", pay.total));
+            for description in &descriptions {
+                let (hours, amount) = pay
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.description == *description)
+                    .fold((0.0, 0.0), |(hours, amount), entry| {
+                        (hours + entry.hours, amount + entry.amount)
+                    });
+                fields.push(hours.to_string());
+                fields.push(format!("{:.2}", amount));
+            }
+        }
+        lines.push(csv_line(&fields));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Fields holding a comma, a quote or a line break are quoted
+fn csv_line(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn hours(seconds: i64) -> f64 {
+    round(seconds as f64 / 3600.0)
+}
+
+fn round(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate};
+
+    use super::*;
+    use crate::wage_bonuses::Period;
+
+    fn datetime(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn csv_has_a_column_for_every_line_of_the_payslip() {
+        let wage_and_bonuses: WageAndBonuses = serde_json::from_str(
+            r#"{
+                "base_rate": 100.0,
+                "period": null,
+                "general_time_periods": [{"bonus_pr_hour": 20.0, "start": "18:00", "end": "24:00"}],
+                "day_of_week_rates": []
+            }"#,
+        )
+        .unwrap();
+        let mut evening = Shift::new(datetime(2, 16), datetime(2, 22));
+        evening.set_id(1);
+        evening.add_break(Duration::minutes(30), None);
+        let mut cancelled = Shift::new(datetime(3, 8), datetime(3, 12));
+        cancelled.set_id(2);
+        cancelled.set_status(ShiftStatus::Cancelled);
+
+        let shifts = vec![
+            ExportedShift::new("bar, inc", &evening, &wage_and_bonuses, None, true).unwrap(),
+            ExportedShift::new("bar, inc", &cancelled, &wage_and_bonuses, None, true).unwrap(),
+        ];
+
+        assert_eq!(
+            export(&shifts, ExportFormat::Csv).unwrap(),
+            "id,job,start,end,break,status,worked_hours,pay,base rate hours,base rate kr.,bonus 18:00-24:00 hours,bonus 18:00-24:00 kr.
+1,\"bar, inc\",2023-10-02 16:00:00,2023-10-02 22:00:00,30,worked,5.5,630.00,5.5,550.00,4,80.00
+2,\"bar, inc\",2023-10-03 08:00:00,2023-10-03 12:00:00,0,cancelled,0,0.00,0,0.00,0,0.00
+"
+        );
+    }
+
+    #[test]
+    fn json_leaves_out_the_pay_unless_asked_for() {
+        let wage_and_bonuses = WageAndBonuses::new(100.0, Period::Month, Vec::new(), Vec::new());
+        let mut shift = Shift::new(datetime(2, 8), datetime(2, 12));
+        shift.set_id(1);
+        let shifts =
+            vec![ExportedShift::new("bar", &shift, &wage_and_bonuses, None, false).unwrap()];
+
+        let json: serde_json::Value =
+            serde_json::from_str(&export(&shifts, ExportFormat::Json).unwrap()).unwrap();
+
+        assert_eq!(json[0]["start"], "2023-10-02 08:00:00");
+        assert_eq!(json[0]["break"], 0);
+        assert!(json[0].get("pay").is_none());
+    }
+}
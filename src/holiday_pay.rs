@@ -0,0 +1,186 @@
+use crate::{
+    calculate::{payslip_from_period, EARNED},
+    error::Result,
+    store::ShiftStore,
+    time::{salery_period_containing, SaleryPeriod},
+    wage_bonuses::{HolidayPay, Period, WageAndBonuses},
+};
+
+/// The holiday pay accrued in a salery period, and what has been accrued up to the end of it
+pub struct HolidayPayPeriod {
+    salery_period: SaleryPeriod,
+    gross: f64,
+    accrued: f64,
+    balance: f64,
+}
+
+impl HolidayPayPeriod {
+    pub fn salery_period(&self) -> &SaleryPeriod {
+        &self.salery_period
+    }
+
+    pub fn gross(&self) -> f64 {
+        self.gross
+    }
+
+    pub fn accrued(&self) -> f64 {
+        self.accrued
+    }
+
+    pub fn balance(&self) -> f64 {
+        self.balance
+    }
+}
+
+/// The holiday pay of the given number of salery periods up to and including the last one, oldest first.
+/// The balance is added up from the period of the first shift, so it doesn't depend on how many periods are shown
+pub fn holiday_pay_history(
+    store: &dyn ShiftStore,
+    job: &str,
+    wage_and_bonuses: &WageAndBonuses,
+    last: &SaleryPeriod,
+    periods: u32,
+) -> Result<Vec<HolidayPayPeriod>> {
+    let period = wage_and_bonuses.period();
+    let mut first_offset = periods.saturating_sub(1);
+    if let Some(first_shift) = store.shifts_in_range(job, None)?.first() {
+        while salery_period_containing(period, last.start().date(), first_offset).start()
+            > first_shift.start()
+        {
+            first_offset += 1;
+        }
+    }
+
+    let mut history = Vec::new();
+    let mut balance = 0.0;
+
+    for offset in (0..=first_offset).rev() {
+        let salery_period = salery_period_containing(period, last.start().date(), offset);
+        let gross =
+            payslip_from_period(store, job, wage_and_bonuses, &salery_period, EARNED)?.salery();
+        let accrued = holiday_pay_accrued(
+            wage_and_bonuses.holiday_pay(),
+            gross,
+            period,
+            &salery_period,
+        );
+        balance += accrued;
+
+        if offset < periods {
+            history.push(HolidayPayPeriod {
+                salery_period,
+                gross,
+                accrued,
+                balance,
+            });
+        }
+    }
+
+    Ok(history)
+}
+
+/// What is accrued as holiday pay in the salery period, in kr. for the holiday allowance and in days for salaried staff
+pub fn holiday_pay_accrued(
+    holiday_pay: &HolidayPay,
+    gross: f64,
+    period: &Period,
+    salery_period: &SaleryPeriod,
+) -> f64 {
+    match holiday_pay {
+        HolidayPay::Allowance { percentage } => gross * percentage / 100.0,
+        HolidayPay::Salaried { days_pr_month } => {
+            days_pr_month * months_in_period(period, salery_period)
+        }
+    }
+}
+
+/// Monthly periods are a whole month, no matter how many days they have
+fn months_in_period(period: &Period, salery_period: &SaleryPeriod) -> f64 {
+    if period.start_day().is_some() {
+        return 1.0;
+    }
+
+    salery_period.interval().duration().num_days() as f64 * 12.0 / 365.0
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Duration, NaiveDate};
+
+    use super::*;
+    use crate::{memory_store::MemoryStore, shift::Shift};
+
+    fn salery_period(days: i64) -> SaleryPeriod {
+        let start = NaiveDate::from_ymd_opt(2023, 10, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        SaleryPeriod::new(start, start + Duration::days(days))
+    }
+
+    #[test]
+    fn holiday_allowance_is_a_share_of_the_gross_pay() {
+        let allowance = HolidayPay::Allowance { percentage: 12.5 };
+
+        let accrued = holiday_pay_accrued(&allowance, 10000.0, &Period::Month, &salery_period(31));
+
+        assert_eq!(accrued, 1250.0);
+    }
+
+    #[test]
+    fn salaried_staff_accrue_days_by_the_month() {
+        let salaried = HolidayPay::Salaried {
+            days_pr_month: 2.08,
+        };
+        let bi_weekly = Period::Days {
+            length_in_days: 14,
+            anchor: NaiveDate::from_ymd_opt(2023, 10, 1).unwrap(),
+        };
+
+        let monthly = holiday_pay_accrued(&salaried, 0.0, &Period::Month, &salery_period(28));
+        let fortnight = holiday_pay_accrued(&salaried, 0.0, &bi_weekly, &salery_period(14));
+
+        assert_eq!(monthly, 2.08);
+        assert!((fortnight - 2.08 * 14.0 * 12.0 / 365.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn balance_adds_up_the_periods_oldest_first() {
+        let mut store = MemoryStore::new();
+        for (month, hours) in [(9, 8), (10, 4)] {
+            let day = NaiveDate::from_ymd_opt(2023, month, 2).unwrap();
+            let shift = Shift::new(
+                day.and_hms_opt(8, 0, 0).unwrap(),
+                day.and_hms_opt(8 + hours, 0, 0).unwrap(),
+            );
+            store.add_shift(&shift, "bar").unwrap();
+        }
+        let wage_and_bonuses = WageAndBonuses::new(100.0, Period::Month, Vec::new(), Vec::new());
+
+        let history =
+            holiday_pay_history(&store, "bar", &wage_and_bonuses, &salery_period(31), 2).unwrap();
+
+        let lines: Vec<(u32, f64, f64, f64)> = history
+            .iter()
+            .map(|period| {
+                (
+                    period.salery_period().start().month(),
+                    period.gross(),
+                    period.accrued(),
+                    period.balance(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![(9, 800.0, 100.0, 100.0), (10, 400.0, 50.0, 150.0)]
+        );
+
+        let last_period =
+            holiday_pay_history(&store, "bar", &wage_and_bonuses, &salery_period(31), 1).unwrap();
+
+        assert_eq!(last_period.len(), 1);
+        assert_eq!(last_period[0].accrued(), 50.0);
+        assert_eq!(last_period[0].balance(), 150.0);
+    }
+}
@@ -0,0 +1,115 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+/// Easter sunday of the given year, found with the anonymous gregorian algorithm
+pub fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+/// The official danish public holidays of the given year.
+/// Store bededag was abolished from 2024
+pub fn danish_holidays(year: i32) -> Vec<(NaiveDate, &'static str)> {
+    let easter = easter_sunday(year);
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+
+    let mut holidays = vec![
+        (date(1, 1), "nytårsdag"),
+        (easter - Duration::days(3), "skærtorsdag"),
+        (easter - Duration::days(2), "langfredag"),
+        (easter, "påskedag"),
+        (easter + Duration::days(1), "2. påskedag"),
+        (easter + Duration::days(39), "kristi himmelfartsdag"),
+        (easter + Duration::days(49), "pinsedag"),
+        (easter + Duration::days(50), "2. pinsedag"),
+        (date(12, 25), "juledag"),
+        (date(12, 26), "2. juledag"),
+    ];
+
+    if year < 2024 {
+        holidays.push((easter + Duration::days(26), "store bededag"));
+    }
+
+    holidays
+}
+
+/// Checks a user defined holiday, either a single date like "2026-12-24" or a date every year like "12-24"
+pub fn matches_holiday_date(holiday: &str, date: NaiveDate) -> bool {
+    let holiday = holiday.trim();
+
+    if let Ok(holiday) = NaiveDate::parse_from_str(holiday, "%Y-%m-%d") {
+        return holiday == date;
+    }
+
+    // chrono can't parse a date without a year, so the year of the date is added
+    match NaiveDate::parse_from_str(&format!("{}-{}", date.year(), holiday), "%Y-%m-%d") {
+        Ok(holiday) => holiday == date,
+        Err(_) => false,
+    }
+}
+
+/// Checks that a user defined holiday can be read as a single date or a date every year.
+/// Dates every year are tried in a leap year, so "02-29" can be used
+pub fn check_holiday_date(holiday: &str) -> Result<(), String> {
+    let holiday = holiday.trim();
+
+    NaiveDate::parse_from_str(holiday, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(&format!("2000-{}", holiday), "%Y-%m-%d"))
+        .map(|_| ())
+        .map_err(|_| {
+            format!(
+                "the holiday \"{}\" should be a date like 2026-12-24 or 12-24",
+                holiday
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn easter_sunday_of_known_years() {
+        assert_eq!(easter_sunday(2019), date(2019, 4, 21));
+        assert_eq!(easter_sunday(2024), date(2024, 3, 31));
+        assert_eq!(easter_sunday(2025), date(2025, 4, 20));
+        assert_eq!(easter_sunday(2026), date(2026, 4, 5));
+    }
+
+    #[test]
+    fn movable_danish_holidays() {
+        let holidays = danish_holidays(2026);
+        let find = |name| holidays.iter().find(|(_, n)| *n == name).unwrap().0;
+
+        assert_eq!(find("2. påskedag"), date(2026, 4, 6));
+        assert_eq!(find("kristi himmelfartsdag"), date(2026, 5, 14));
+        assert_eq!(find("2. pinsedag"), date(2026, 5, 25));
+        assert!(holidays.iter().all(|(_, name)| *name != "store bededag"));
+        assert!(danish_holidays(2023).contains(&(date(2023, 5, 5), "store bededag")));
+    }
+
+    #[test]
+    fn user_defined_holidays_with_and_without_year() {
+        assert!(matches_holiday_date("2026-12-24", date(2026, 12, 24)));
+        assert!(!matches_holiday_date("2026-12-24", date(2027, 12, 24)));
+        assert!(matches_holiday_date("12-31", date(2027, 12, 31)));
+        assert!(!matches_holiday_date("not a date", date(2027, 12, 31)));
+    }
+}
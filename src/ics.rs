@@ -0,0 +1,432 @@
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::{
+    error::{Error, Result},
+    export::ExportedShift,
+    import::ImportedShift,
+    shift::{Shift, ShiftStatus},
+    time::Interval,
+    wage_bonuses::WageAndBonuses,
+};
+
+/// An event read from an iCalendar file, along with the line it started on
+pub struct IcsEvent {
+    pub line: usize,
+    pub summary: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub cancelled: bool,
+}
+
+/// The name, parameters and value of a line in an event
+type Property = (String, String, String);
+
+/// A calendar with an event for every shift, with the estimated pay in its description.
+/// Times are written without a time zone, so calendars show them in local time like they are stored
+pub fn to_ics(shifts: &[ExportedShift], stamp: NaiveDateTime) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//salery_calculator//shifts//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    for shift in shifts {
+        let mut description = format!("Break: {} minutes", shift.break_minutes());
+        if let Some(pay) = shift.pay() {
+            description = format!(
+                "Estimated pay: {:.2} kr. for {} hours\n{}",
+                pay.total(),
+                pay.worked_hours(),
+                description
+            );
+        }
+
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:shift-{}@salery_calculator", shift.id()),
+            format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+            format!("DTSTART:{}", shift.start().format("%Y%m%dT%H%M%S")),
+            format!("DTEND:{}", shift.end().format("%Y%m%dT%H%M%S")),
+            format!("SUMMARY:{}", escape(&format!("Shift at {}", shift.job()))),
+            format!("DESCRIPTION:{}", escape(&description)),
+            format!(
+                "STATUS:{}",
+                match shift.status() {
+                    ShiftStatus::Planned => "TENTATIVE",
+                    ShiftStatus::Worked => "CONFIRMED",
+                    ShiftStatus::Cancelled => "CANCELLED",
+                }
+            ),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .map(|line| fold(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n"
+}
+
+/// Reads every event of the calendar. Events without a start and end time, like all day events,
+/// events that don't end after they start and events that repeat are described in the errors.
+/// Times with a TZID are read as if the time zone was the local one, see `parse_date_time`
+pub fn read_events(text: &str) -> (Vec<IcsEvent>, Vec<String>) {
+    let mut events = Vec::new();
+    let mut errors = Vec::new();
+    let mut event: Option<(usize, Vec<Property>)> = None;
+
+    for (line, content) in unfold(text) {
+        let Some((name_and_params, value)) = content.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name_and_params
+            .split_once(';')
+            .unwrap_or((name_and_params, ""));
+        let name = name.to_uppercase();
+
+        match (name.as_str(), value.trim()) {
+            ("BEGIN", "VEVENT") => event = Some((line, Vec::new())),
+            ("END", "VEVENT") => {
+                if let Some((line, properties)) = event.take() {
+                    match event_from_properties(line, &properties) {
+                        Ok(event) => events.push(event),
+                        Err(err) => errors.push(format!("line {}: {}", line, err)),
+                    }
+                }
+            }
+            _ => {
+                if let Some((_, properties)) = event.as_mut() {
+                    properties.push((name, params.to_uppercase(), value.to_string()));
+                }
+            }
+        }
+    }
+
+    (events, errors)
+}
+
+/// The events whose summary contains the given text and that start in the range, as shifts of the job with the given status.
+/// Cancelled events become cancelled shifts, and the break comes from the rules of the job
+pub fn shifts_from_events(
+    events: Vec<IcsEvent>,
+    summary: Option<&str>,
+    range: &Interval,
+    job: &str,
+    wage_and_bonuses: &WageAndBonuses,
+    status: ShiftStatus,
+) -> Vec<ImportedShift> {
+    events
+        .into_iter()
+        .filter(|event| {
+            summary.is_none_or(|summary| {
+                event
+                    .summary
+                    .to_lowercase()
+                    .contains(&summary.to_lowercase())
+            })
+        })
+        .filter(|event| range.contains(event.start))
+        .map(|event| {
+            let mut shift = Shift::new(event.start, event.end);
+            shift.add_break(wage_and_bonuses.break_or_rule(&shift, None), None);
+            shift.set_status(if event.cancelled {
+                ShiftStatus::Cancelled
+            } else {
+                status
+            });
+
+            ImportedShift {
+                line: event.line,
+                job: job.to_string(),
+                shift,
+            }
+        })
+        .collect()
+}
+
+fn event_from_properties(line: usize, properties: &[Property]) -> Result<IcsEvent> {
+    let property = |name: &str| {
+        properties
+            .iter()
+            .find(|(property, _, _)| property == name)
+            .map(|(_, params, value)| (params.as_str(), value.as_str()))
+    };
+
+    let summary = property("SUMMARY").map_or(String::new(), |(_, value)| unescape(value));
+    // only the first time of a repeating event is written in it, so adding it would leave out the rest
+    if property("RRULE").is_some() || property("RDATE").is_some() {
+        return Err(Error::Parse(format!(
+            "the event \"{}\" repeats, which can't be imported. Add its shifts with schedule instead",
+            summary
+        )));
+    }
+    let Some(start) = property("DTSTART") else {
+        return Err(Error::Parse(format!(
+            "the event \"{}\" has no start",
+            summary
+        )));
+    };
+    let start = parse_date_time(start.0, start.1)?;
+    let end = match (property("DTEND"), property("DURATION")) {
+        (Some((params, value)), _) => parse_date_time(params, value)?,
+        (None, Some((_, duration))) => start + parse_duration(duration)?,
+        (None, None) => {
+            return Err(Error::Parse(format!(
+                "the event \"{}\" has no end",
+                summary
+            )))
+        }
+    };
+    // checked before anything is added, like the rows of a csv import
+    if end <= start {
+        return Err(Error::Validation(format!(
+            "the event \"{}\" ends at {} before it starts at {}",
+            summary, end, start
+        )));
+    }
+
+    Ok(IcsEvent {
+        line,
+        summary,
+        start,
+        end,
+        cancelled: property("STATUS")
+            .is_some_and(|(_, value)| value.eq_ignore_ascii_case("CANCELLED")),
+    })
+}
+
+/// Times in UTC are moved to local time. Time zones aren't looked up, so a time with a TZID, eg. TZID=Europe/Copenhagen,
+/// is taken to already be in local time, like times without a time zone. That is right for rotas made where the shifts are worked
+fn parse_date_time(params: &str, value: &str) -> Result<NaiveDateTime> {
+    let value = value.trim();
+    if params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME") {
+        return Err(Error::Parse(format!(
+            "{} is a whole day, not a shift",
+            value
+        )));
+    }
+
+    let parsed = match value.strip_suffix('Z') {
+        Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map(|utc| {
+            Utc.from_utc_datetime(&utc)
+                .with_timezone(&Local)
+                .naive_local()
+        }),
+        None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S"),
+    };
+
+    parsed.map_err(|_| {
+        Error::Parse(match NaiveDate::parse_from_str(value, "%Y%m%d") {
+            Ok(_) => format!("{} is a whole day, not a shift", value),
+            Err(_) => format!("couldn't read \"{}\" as a date and time", value),
+        })
+    })
+}
+
+/// Reads durations like PT8H30M
+fn parse_duration(value: &str) -> Result<Duration> {
+    let err = || Error::Parse(format!("couldn't read \"{}\" as a duration", value));
+    let mut rest = value.trim().strip_prefix("P").ok_or_else(err)?;
+    let mut duration = Duration::zero();
+    let mut in_time = false;
+
+    while !rest.is_empty() {
+        if let Some(time) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = time;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(err)?;
+        let amount: i64 = rest[..digits].parse().map_err(|_| err())?;
+        let unit = rest[digits..].chars().next().ok_or_else(err)?;
+        let part = match (unit, in_time) {
+            ('W', false) => Duration::weeks(amount),
+            ('D', false) => Duration::days(amount),
+            ('H', true) => Duration::hours(amount),
+            ('M', true) => Duration::minutes(amount),
+            ('S', true) => Duration::seconds(amount),
+            _ => return Err(err()),
+        };
+        duration += part;
+        rest = &rest[digits + unit.len_utf8()..];
+    }
+
+    Ok(duration)
+}
+
+/// Lines starting with a space or tab continue the line before it
+fn unfold(text: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continued), Some((_, last))) => last.push_str(continued),
+            _ => lines.push((index + 1, line.to_string())),
+        }
+    }
+
+    lines
+}
+
+/// Lines are kept to 75 bytes, continuing on the next line after a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{time::days_between, wage_bonuses::Period};
+
+    fn datetime(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn exported_shifts_are_read_back_as_events() {
+        let wage_and_bonuses = WageAndBonuses::new(100.0, Period::Month, Vec::new(), Vec::new());
+        let mut shift = Shift::new(datetime(2, 22), datetime(3, 6));
+        shift.add_break(Duration::minutes(30), None);
+        shift.set_status(ShiftStatus::Planned);
+        shift.set_id(7);
+        let shifts = vec![ExportedShift::new(
+            "bar; the one downtown, with a very long name that needs folding",
+            &shift,
+            &wage_and_bonuses,
+            None,
+            true,
+        )
+        .unwrap()];
+
+        let calendar = to_ics(&shifts, datetime(1, 12));
+        assert!(calendar
+            .contains("DESCRIPTION:Estimated pay: 750.00 kr. for 7.5 hours\\nBreak: 30 minutes"));
+        assert!(calendar.lines().all(|line| line.len() <= 76));
+
+        let (events, errors) = read_events(&calendar);
+        assert!(errors.is_empty());
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].summary,
+            "Shift at bar; the one downtown, with a very long name that needs folding"
+        );
+        assert_eq!(events[0].start, datetime(2, 22));
+        assert_eq!(events[0].end, datetime(3, 6));
+        assert!(!events[0].cancelled);
+    }
+
+    #[test]
+    fn events_with_a_duration_and_whole_days() {
+        let calendar = "BEGIN:VCALENDAR
+BEGIN:VEVENT
+SUMMARY:Evening
+DTSTART;TZID=Europe/Copenhagen:20231002T140000
+DURATION:PT8H
+STATUS:CANCELLED
+END:VEVENT
+BEGIN:VEVENT
+SUMMARY:Day off
+DTSTART;VALUE=DATE:20231003
+DTEND;VALUE=DATE:20231004
+END:VEVENT
+BEGIN:VEVENT
+SUMMARY:Backwards
+DTSTART:20231004T160000
+DTEND:20231004T080000
+END:VEVENT
+BEGIN:VEVENT
+SUMMARY:Empty
+DTSTART:20231005T080000
+DURATION:PT0H
+END:VEVENT
+BEGIN:VEVENT
+SUMMARY:Every monday
+DTSTART:20231002T080000
+DTEND:20231002T160000
+RRULE:FREQ=WEEKLY;BYDAY=MO
+END:VEVENT
+END:VCALENDAR";
+
+        let (events, errors) = read_events(calendar);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].start, datetime(2, 14));
+        assert_eq!(events[0].end, datetime(2, 22));
+        assert!(events[0].cancelled);
+        assert_eq!(errors.len(), 4);
+        assert_eq!(errors[0], "line 8: 20231003 is a whole day, not a shift");
+        assert!(errors[1].starts_with("line 13: the event \"Backwards\" ends at"));
+        assert!(errors[2].starts_with("line 18: the event \"Empty\" ends at"));
+        assert!(errors[3].starts_with("line 23: the event \"Every monday\" repeats"));
+
+        let wage_and_bonuses = WageAndBonuses::new(100.0, Period::Month, Vec::new(), Vec::new());
+        let october = days_between(
+            NaiveDate::from_ymd_opt(2023, 10, 1),
+            NaiveDate::from_ymd_opt(2023, 10, 31),
+        );
+        let shifts = shifts_from_events(
+            events,
+            Some("evening"),
+            &october,
+            "bar",
+            &wage_and_bonuses,
+            ShiftStatus::Planned,
+        );
+        assert_eq!(shifts.len(), 1);
+        assert_eq!((shifts[0].line, shifts[0].job.as_str()), (2, "bar"));
+        assert_eq!(shifts[0].shift.status(), ShiftStatus::Cancelled);
+    }
+
+    #[test]
+    fn durations_that_cant_be_read() {
+        assert_eq!(parse_duration("PT8H30M"), Ok(Duration::minutes(510)));
+        for duration in ["PT8é", "PT8", "8H", "PT8D"] {
+            assert!(matches!(parse_duration(duration), Err(Error::Parse(_))));
+        }
+    }
+}
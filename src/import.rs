@@ -0,0 +1,428 @@
+use chrono::Duration;
+
+use crate::{
+    error::Result,
+    shift::Shift,
+    store::ShiftStore,
+    time::{parse_naivedatetime_with_formats, DATETIME_FORMATS},
+    wage_bonuses::Jobs,
+};
+
+/// How the shifts are laid out in the csv file. Columns are found by their name in the header
+pub struct ImportSettings {
+    pub start_column: String,
+    pub end_column: String,
+    /// When the date has its own column, the start and end columns only hold the time of day
+    pub date_column: Option<String>,
+    pub break_column: Option<String>,
+    pub job_column: Option<String>,
+    pub status_column: Option<String>,
+    pub delimiter: char,
+    /// Tried before the usual formats
+    pub formats: Vec<String>,
+    /// The job of rows without a job in the job column, or of every row without a job column.
+    /// When it is left out, every row has to have its job in the job column
+    pub job: Option<String>,
+}
+
+/// A valid row of the csv file, along with the line it started on
+pub struct ImportedShift {
+    pub line: usize,
+    pub job: String,
+    pub shift: Shift,
+}
+
+/// Reads the shifts from the csv file. Rows that can't be read are left out, and described in the errors along with their line number
+pub fn read_shifts(
+    text: &str,
+    settings: &ImportSettings,
+    jobs: &Jobs,
+) -> (Vec<ImportedShift>, Vec<String>) {
+    let records = match parse_csv(text, settings.delimiter) {
+        Ok(records) => records,
+        Err(err) => return (Vec::new(), vec![err]),
+    };
+    let Some(((_, header), rows)) = records.split_first() else {
+        return (Vec::new(), vec!["the file is empty".to_string()]);
+    };
+
+    let columns = match Columns::find(header, settings) {
+        Ok(columns) => columns,
+        Err(err) => return (Vec::new(), vec![err]),
+    };
+
+    let mut formats: Vec<&str> = settings.formats.iter().map(|f| f.as_str()).collect();
+    formats.extend(DATETIME_FORMATS);
+
+    let mut shifts = Vec::new();
+    let mut errors = Vec::new();
+    for (line, row) in rows {
+        match read_row(row, &columns, settings, &formats, jobs) {
+            Ok((job, shift)) => shifts.push(ImportedShift {
+                line: *line,
+                job,
+                shift,
+            }),
+            Err(err) => errors.push(format!("line {}: {}", line, err)),
+        }
+    }
+
+    (shifts, errors)
+}
+
+/// Where the same shift was found before
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Duplicate {
+    InFile,
+    InStore,
+}
+
+/// The shifts that were added, or would have been in a dry run, and the ones skipped as duplicates
+pub struct ImportOutcome<'a> {
+    pub added: Vec<&'a ImportedShift>,
+    pub duplicates: Vec<(&'a ImportedShift, Duplicate)>,
+}
+
+/// Adds the shifts that aren't already in the store or earlier in the file. Nothing is added in a dry run
+pub fn add_imported_shifts<'a>(
+    store: &mut dyn ShiftStore,
+    shifts: &'a [ImportedShift],
+    dry_run: bool,
+) -> Result<ImportOutcome<'a>> {
+    let mut added: Vec<&ImportedShift> = Vec::new();
+    let mut duplicates = Vec::new();
+
+    for imported in shifts {
+        let (start, end) = (imported.shift.start(), imported.shift.end());
+        let in_file = added.iter().any(|other| {
+            other.job == imported.job && other.shift.start() == start && other.shift.end() == end
+        });
+
+        if in_file {
+            duplicates.push((imported, Duplicate::InFile));
+        } else if store.has_shift(&imported.job, start, end)? {
+            duplicates.push((imported, Duplicate::InStore));
+        } else {
+            added.push(imported);
+        }
+    }
+
+    if !dry_run {
+        for imported in &added {
+            store.add_shift(&imported.shift, &imported.job)?;
+        }
+    }
+
+    Ok(ImportOutcome { added, duplicates })
+}
+
+/// The index of every column used
+struct Columns {
+    start: usize,
+    end: usize,
+    date: Option<usize>,
+    break_minutes: Option<usize>,
+    job: Option<usize>,
+    status: Option<usize>,
+}
+
+impl Columns {
+    fn find(header: &[String], settings: &ImportSettings) -> std::result::Result<Self, String> {
+        let find = |name: &str| {
+            header
+                .iter()
+                .position(|column| column.trim().eq_ignore_ascii_case(name.trim()))
+                .ok_or(format!("there is no column called {} in the header", name))
+        };
+        let find_optional = |name: &Option<String>| name.as_deref().map(find).transpose();
+
+        Ok(Columns {
+            start: find(&settings.start_column)?,
+            end: find(&settings.end_column)?,
+            date: find_optional(&settings.date_column)?,
+            break_minutes: find_optional(&settings.break_column)?,
+            job: find_optional(&settings.job_column)?,
+            status: find_optional(&settings.status_column)?,
+        })
+    }
+}
+
+fn read_row(
+    row: &[String],
+    columns: &Columns,
+    settings: &ImportSettings,
+    formats: &[&str],
+    jobs: &Jobs,
+) -> std::result::Result<(String, Shift), String> {
+    let cell = |index: usize| row.get(index).map(|cell| cell.trim()).unwrap_or("");
+    let optional_cell = |index: Option<usize>| index.map(cell).filter(|cell| !cell.is_empty());
+
+    let Some(job) = optional_cell(columns.job).or(settings.job.as_deref()) else {
+        return Err(
+            "the row has no job, write it in the job column or give it with --job".to_string(),
+        );
+    };
+    let job = job.to_string();
+    let Some(wage_and_bonuses) = jobs.get(&job) else {
+        return Err(format!("there is no job called {}", job));
+    };
+
+    let date_time = |index: usize| {
+        let value = match optional_cell(columns.date) {
+            Some(date) => format!("{} {}", date, cell(index)),
+            None => cell(index).to_string(),
+        };
+        parse_naivedatetime_with_formats(&value, formats)
+            .map_err(|_| format!("couldn't read \"{}\" as a date and time", value))
+    };
+    let start = date_time(columns.start)?;
+    let mut end = date_time(columns.end)?;
+
+    // with the date in its own column, a shift ending before it started ended the day after
+    if columns.date.is_some() && end <= start {
+        end += Duration::days(1);
+    }
+    if end <= start {
+        return Err(format!(
+            "the shift ends at {} before it starts at {}",
+            end, start
+        ));
+    }
+
+    let mut shift = Shift::new(start, end);
+
+    let break_duration = match optional_cell(columns.break_minutes) {
+        Some(minutes) => match minutes.parse::<i64>() {
+            Ok(minutes) if minutes >= 0 => Duration::minutes(minutes),
+            _ => {
+                return Err(format!(
+                    "the break \"{}\" is not a whole number of minutes",
+                    minutes
+                ))
+            }
+        },
+        None => wage_and_bonuses
+            .breaks()
+            .automatic_break(shift.interval().duration()),
+    };
+    if break_duration > shift.interval().duration() {
+        return Err("the break is longer than the shift".to_string());
+    }
+    shift.add_break(break_duration, None);
+
+    if let Some(status) = optional_cell(columns.status) {
+        shift.set_status(status.parse()?);
+    }
+
+    Ok((job, shift))
+}
+
+/// Splits the text into records of fields, along with the line each record starts on.
+/// Fields can be quoted to hold the delimiter, line breaks or quotes written twice, like in most spreadsheets
+fn parse_csv(
+    text: &str,
+    delimiter: char,
+) -> std::result::Result<Vec<(usize, Vec<String>)>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            '\n' if in_quotes => {
+                field.push(c);
+                line += 1;
+            }
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|field| !field.trim().is_empty()) {
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                record_line = line;
+            }
+            '\r' if !in_quotes => {}
+            c if c == delimiter && !in_quotes => record.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("line {}: the quote is never closed", record_line));
+    }
+
+    record.push(field);
+    if record.iter().any(|field| !field.trim().is_empty()) {
+        records.push((record_line, record));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::*;
+    use crate::{
+        memory_store::MemoryStore,
+        shift::ShiftStatus,
+        wage_bonuses::{Period, WageAndBonuses},
+    };
+
+    fn datetime(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn settings() -> ImportSettings {
+        ImportSettings {
+            start_column: "start".to_string(),
+            end_column: "end".to_string(),
+            date_column: None,
+            break_column: Some("break".to_string()),
+            job_column: None,
+            status_column: None,
+            delimiter: ',',
+            formats: Vec::new(),
+            job: Some("bar".to_string()),
+        }
+    }
+
+    fn jobs() -> Jobs {
+        let mut jobs = Jobs::new();
+        jobs.insert(
+            "bar".to_string(),
+            WageAndBonuses::new(100.0, Period::Month, Vec::new(), Vec::new()),
+        );
+        jobs
+    }
+
+    #[test]
+    fn quoted_fields_keep_delimiters_quotes_and_line_breaks() {
+        let records = parse_csv("a,\"b, \"\"c\"\"\"\r\n\n\"d\ne\",f\ng,h", ',').unwrap();
+
+        assert_eq!(
+            records,
+            vec![
+                (1, vec!["a".to_string(), "b, \"c\"".to_string()]),
+                (3, vec!["d\ne".to_string(), "f".to_string()]),
+                (5, vec!["g".to_string(), "h".to_string()]),
+            ]
+        );
+        assert!(parse_csv("a,\"b", ',').is_err());
+    }
+
+    #[test]
+    fn bad_rows_are_reported_with_their_line_number() {
+        let text = "Start,End,Break
+02-10-2023 08:00,02-10-2023 16:00,30
+03-10-2023 16:00,03-10-2023 08:00,
+not a date,04-10-2023 16:00,
+05-10-2023 08:00,05-10-2023 16:00,half an hour";
+
+        let (shifts, errors) = read_shifts(text, &settings(), &jobs());
+
+        assert_eq!(shifts.len(), 1);
+        assert_eq!(shifts[0].line, 2);
+        assert_eq!(shifts[0].shift.start(), datetime(2, 8));
+        assert_eq!(shifts[0].shift.break_duration(), Duration::minutes(30));
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("line 3:"));
+        assert!(errors[1].starts_with("line 4:"));
+        assert!(errors[2].starts_with("line 5:"));
+    }
+
+    #[test]
+    fn date_column_with_times_and_custom_format() {
+        let settings = ImportSettings {
+            date_column: Some("date".to_string()),
+            break_column: None,
+            status_column: Some("status".to_string()),
+            delimiter: ';',
+            formats: vec!["%d/%m/%Y %H.%M".to_string()],
+            ..settings()
+        };
+        let text = "date;start;end;status\n02/10/2023;22.00;06.00;planned";
+
+        let (shifts, errors) = read_shifts(text, &settings, &jobs());
+
+        assert!(errors.is_empty());
+        assert_eq!(shifts[0].shift.start(), datetime(2, 22));
+        assert_eq!(shifts[0].shift.end(), datetime(3, 6));
+        assert_eq!(shifts[0].shift.status(), ShiftStatus::Planned);
+    }
+
+    #[test]
+    fn dates_with_the_year_in_front_are_read_month_first() {
+        let text = "start,end,break\n2023-10-02 08:00,2023-10-02 16:00,\n2023-10-03 08:00:00,2023-10-03 16:00:00,";
+
+        let (shifts, errors) = read_shifts(text, &settings(), &jobs());
+
+        assert!(errors.is_empty());
+        assert_eq!(shifts[0].shift.start(), datetime(2, 8));
+        assert_eq!(shifts[1].shift.end(), datetime(3, 16));
+    }
+
+    #[test]
+    fn rows_without_a_job_need_a_job_to_fall_back_to() {
+        let settings = ImportSettings {
+            job_column: Some("job".to_string()),
+            job: None,
+            ..settings()
+        };
+        let text = "start,end,break,job
+02-10-2023 08:00,02-10-2023 16:00,,bar
+03-10-2023 08:00,03-10-2023 16:00,,";
+
+        let (shifts, errors) = read_shifts(text, &settings, &jobs());
+
+        assert_eq!(shifts.len(), 1);
+        assert_eq!(shifts[0].job, "bar");
+        assert_eq!(
+            errors,
+            vec!["line 3: the row has no job, write it in the job column or give it with --job"]
+        );
+    }
+
+    #[test]
+    fn shifts_already_in_the_store_or_the_file_are_skipped() {
+        let mut store = MemoryStore::new();
+        store
+            .add_shift(&Shift::new(datetime(2, 8), datetime(2, 16)), "bar")
+            .unwrap();
+        let text = "start,end,break
+02-10-2023 08:00,02-10-2023 16:00,
+03-10-2023 08:00,03-10-2023 16:00,
+03-10-2023 08:00,03-10-2023 16:00,30";
+        let (shifts, _) = read_shifts(text, &settings(), &jobs());
+
+        let outcome = add_imported_shifts(&mut store, &shifts, true).unwrap();
+        assert_eq!(outcome.added.len(), 1);
+        let duplicates: Vec<(usize, Duplicate)> = outcome
+            .duplicates
+            .iter()
+            .map(|(imported, duplicate)| (imported.line, *duplicate))
+            .collect();
+        assert_eq!(
+            duplicates,
+            vec![(2, Duplicate::InStore), (4, Duplicate::InFile)]
+        );
+        assert_eq!(store.shifts_in_range("bar", None).unwrap().len(), 1);
+
+        add_imported_shifts(&mut store, &shifts, false).unwrap();
+        assert_eq!(store.shifts_in_range("bar", None).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+
+use crate::{
+    error::{Error, Result},
+    memory_store::MemoryStore,
+    shift::Shift,
+    store::ShiftStore,
+    time::SaleryPeriod,
+};
+
+/// Keeps the shifts in a plain json file, with a shift on every few lines, so it can be read, diffed and kept in git.
+/// The whole file is written again after every change
+pub struct JsonStore {
+    path: PathBuf,
+    shifts: MemoryStore,
+}
+
+impl JsonStore {
+    /// The file is created the first time a shift is added
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let shifts = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|err| {
+                Error::Database(format!("couldn't read {}: {}", path.display(), err))
+            })?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => MemoryStore::new(),
+            Err(err) => {
+                return Err(Error::Database(format!(
+                    "couldn't read {}: {}",
+                    path.display(),
+                    err
+                )))
+            }
+        };
+
+        Ok(Self { path, shifts })
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.shifts).map_err(|err| {
+            Error::Database(format!("couldn't write the shifts as json: {}", err))
+        })?;
+
+        std::fs::write(&self.path, json + "\n").map_err(|err| {
+            Error::Database(format!("couldn't save {}: {}", self.path.display(), err))
+        })
+    }
+}
+
+impl ShiftStore for JsonStore {
+    fn add_shift(&mut self, shift: &Shift, job: &str) -> Result<()> {
+        self.shifts.add_shift(shift, job)?;
+        self.save()
+    }
+
+    /// The file is written once, after every shift has been added
+    fn add_shifts(&mut self, shifts: &[Shift], job: &str) -> Result<()> {
+        self.shifts.add_shifts(shifts, job)?;
+        self.save()
+    }
+
+    fn update_shift(&mut self, shift: &Shift) -> Result<()> {
+        self.shifts.update_shift(shift)?;
+        self.save()
+    }
+
+    fn remove_shift(&mut self, shift_id: i64) -> Result<()> {
+        self.shifts.remove_shift(shift_id)?;
+        self.save()
+    }
+
+    fn shifts_in_range(
+        &self,
+        job: &str,
+        salery_period: Option<&SaleryPeriod>,
+    ) -> Result<Vec<Shift>> {
+        self.shifts.shifts_in_range(job, salery_period)
+    }
+
+    fn shift_by_id(&self, shift_id: i64) -> Result<Option<Shift>> {
+        self.shifts.shift_by_id(shift_id)
+    }
+
+    fn clock_in(&mut self, start: NaiveDateTime, job: &str) -> Result<()> {
+        self.shifts.clock_in(start, job)?;
+        self.save()
+    }
+
+    fn open_shift(&self) -> Result<Option<(i64, String, NaiveDateTime)>> {
+        self.shifts.open_shift()
+    }
+
+    fn remove_all(&mut self) -> Result<()> {
+        self.shifts.remove_all()?;
+        self.save()
+    }
+}
@@ -0,0 +1,90 @@
+//! Keeps track of shifts and calculates the salery earned from them, using the wage and bonuses of the job.
+//!
+//! The pay can be calculated straight from shifts, without a database:
+//!
+//! ```
+//! use chrono::NaiveDate;
+//! use salery_calculator::{
+//!     payslip_from_shifts, Bonus, Period, SaleryPeriod, Shift, WageAndBonuses,
+//! };
+//!
+//! let evening_bonus = Bonus::new(20.0, "18:00".to_string(), "24:00".to_string(), None)?;
+//! let wage_and_bonuses = WageAndBonuses::new(100.0, Period::Month, vec![evening_bonus], Vec::new());
+//!
+//! let day = NaiveDate::from_ymd_opt(2023, 10, 2).unwrap();
+//! let shift = Shift::new(
+//!     day.and_hms_opt(16, 0, 0).unwrap(),
+//!     day.and_hms_opt(22, 0, 0).unwrap(),
+//! );
+//! let october = SaleryPeriod::new(
+//!     NaiveDate::from_ymd_opt(2023, 10, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+//!     NaiveDate::from_ymd_opt(2023, 11, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+//! );
+//!
+//! let payslip = payslip_from_shifts(&[shift], &wage_and_bonuses, &october);
+//! assert_eq!(payslip.salery(), 6.0 * 100.0 + 4.0 * 20.0);
+//! # Ok::<(), salery_calculator::Error>(())
+//! ```
+//!
+//! Shifts are kept in a [`ShiftStore`], either a sqlite [`Database`], a [`JsonStore`] or a [`MemoryStore`],
+//! and the wage and bonuses of every job are read from a json file with [`Jobs::open`].
+
+/// The payslip of a salery period, with a line for every bonus and overtime
+mod calculate;
+/// The sqlite database the shifts are stored in
+mod database;
+/// The errors of the crate, each with the exit code the program ends with
+mod error;
+/// Writes shifts as csv, json or ics
+mod export;
+/// The holiday pay earned in a salery period
+mod holiday_pay;
+/// Public holidays, and the days the config adds to them
+mod holidays;
+/// Reads and writes iCalendar files
+mod ics;
+/// Reads shifts from csv files
+mod import;
+/// Keeps the shifts in a json file
+mod json_store;
+/// Keeps the shifts in memory
+mod memory_store;
+/// The versions of the database and how to upgrade it
+mod migrations;
+/// A shift along with its break and status
+mod shift;
+/// The ways shifts can be stored
+mod store;
+/// Estimates the tax taken from the pay
+mod tax;
+/// Salery periods and reading dates and times
+mod time;
+/// The config of every job, with its wage, bonuses, salery period and rules
+mod wage_bonuses;
+
+pub use calculate::{
+    forecast, payslip_from_period, payslip_from_shifts, Payslip, PeriodForecast, EARNED, PROJECTED,
+};
+pub use database::Database;
+pub use error::{Error, Result};
+pub use export::{export, exported_shifts, ExportFormat, ExportedShift};
+pub use holiday_pay::{holiday_pay_accrued, holiday_pay_history, HolidayPayPeriod};
+pub use ics::{read_events, shifts_from_events, IcsEvent};
+pub use import::{
+    add_imported_shifts, read_shifts, Duplicate, ImportOutcome, ImportSettings, ImportedShift,
+};
+pub use json_store::JsonStore;
+pub use memory_store::MemoryStore;
+pub use migrations::{Migration, Upgrade};
+pub use shift::{Shift, ShiftStatus};
+pub use store::ShiftStore;
+pub use tax::{net_pay, NetPay};
+pub use time::{
+    current_salery_period, dates_on_weekdays, days_between, now, parse_date,
+    parse_naivedatetime_from_str, parse_time_during_shift, salery_period_containing,
+    salery_period_from_offset, Interval, SaleryPeriod,
+};
+pub use wage_bonuses::{
+    Bonus, BreakRule, HolidayPay, Jobs, Period, RateChange, ShiftTemplate, WageAndBonuses,
+    DEFAULT_JOB,
+};
@@ -1,142 +1,642 @@
-mod calculate;
 mod cli;
-mod database;
 mod setup;
-mod time;
-mod wage_bonuses;
 
-use calculate::calculate_salery_from_period;
-use chrono::Duration;
+use chrono::{Datelike, Duration, NaiveDateTime, Weekday};
 use clap::Parser;
-use cli::{Cli, Operation};
-use database::Database;
-use setup::setup_wage_bonuses_if_missing;
-use time::{current_salery_period, SQLformat};
-use wage_bonuses::WageAndBonuses;
-
-use crate::{
-    calculate::duration_worked,
-    time::{parse_naivedatetime_from_str, salery_period_from_offset},
+use cli::{Cli, DbOperation, Operation, StoreKind};
+use salery_calculator::{
+    add_imported_shifts, current_salery_period, dates_on_weekdays, days_between, export,
+    exported_shifts, forecast, holiday_pay_history, net_pay, now, parse_date,
+    parse_naivedatetime_from_str, parse_time_during_shift, payslip_from_period,
+    payslip_from_shifts, read_events, read_shifts, salery_period_from_offset, shifts_from_events,
+    Database, Duplicate, Error, ExportFormat, HolidayPay, ImportOutcome, ImportSettings, Jobs,
+    JsonStore, RateChange, Result, SaleryPeriod, Shift, ShiftStore, ShiftTemplate, Upgrade,
+    WageAndBonuses, EARNED, PROJECTED,
 };
+use setup::{default_break_minutes, setup_wage_bonuses, setup_wage_bonuses_if_missing};
 
 fn main() {
-    let exe_directory = std::env::current_exe().expect("couldn't find the directory of the exe");
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        std::process::exit(err.exit_code());
+    }
+}
+
+fn run() -> Result<()> {
+    let exe_directory = std::env::current_exe()
+        .map_err(|err| Error::Io(format!("couldn't find the directory of the exe: {}", err)))?;
 
     let db_path = exe_directory.with_file_name("DB.db");
     let wage_bonuses_path = exe_directory.with_file_name("Wage_bonuses_map.json");
 
-    setup_wage_bonuses_if_missing(&wage_bonuses_path);
+    setup_wage_bonuses_if_missing(&wage_bonuses_path)?;
 
     let cli = Cli::parse();
 
-    let db = Database::open_or_create_db(db_path, "shifts");
-    let wage_and_bonuses = WageAndBonuses::open(&wage_bonuses_path).unwrap();
+    // the db command shows the version before upgrading it, so the database isn't opened as usual
+    if let Some(Operation::Db { operation }) = cli.operation() {
+        return manage_database(&Database::open(db_path, "shifts")?, operation);
+    }
+
+    let mut db: Box<dyn ShiftStore> = match cli.store() {
+        StoreKind::Sqlite => {
+            let db = Database::open(db_path, "shifts")?;
+            print_upgrade(&db.migrate()?);
+            Box::new(db)
+        }
+        StoreKind::Json => Box::new(JsonStore::open(
+            exe_directory.with_file_name("shifts.json"),
+        )?),
+    };
+    let mut jobs = Jobs::open(&wage_bonuses_path)?;
+    if let Some(minutes) = default_break_minutes(exe_directory.with_file_name(".env"))? {
+        jobs.set_default_break(minutes);
+    }
 
-    let Some(op) = cli.operation() else { return };
+    let Some(op) = cli.operation() else {
+        return Ok(());
+    };
     match op {
-        Operation::List { all, sort, offset } => {
-            let salery_period = match offset {
-                Some(offset) => salery_period_from_offset(&wage_and_bonuses, *offset),
-                None => current_salery_period(&wage_and_bonuses),
-            };
+        Operation::List {
+            all,
+            sort,
+            offset,
+            job,
+        } => {
+            for (job, wage_and_bonuses) in jobs.selected(job.as_deref())? {
+                let salery_period =
+                    salery_period_from_offset(wage_and_bonuses, offset.unwrap_or(0));
+
+                if jobs.len() > 1 {
+                    println!("Job: {}", job);
+                }
+
+                let mut shifts = db.shifts_in_range(job, (!all).then_some(&salery_period))?;
+                if *sort {
+                    shifts.reverse();
+                }
+
+                for shift in shifts {
+                    println!(
+                        "id: {} | shift start: {} | shift end: {} | break: {} minutes | {}",
+                        shift.id().unwrap_or_default(),
+                        shift.start(),
+                        shift.end(),
+                        shift.break_duration().num_minutes(),
+                        shift.status()
+                    );
+                }
+
+                if let Some((id, open_job, start)) = db.open_shift()? {
+                    if open_job == job && (*all || salery_period.interval().contains(start)) {
+                        println!(
+                            "id: {} | shift start: {} | shift end: clocked in",
+                            id, start
+                        );
+                    }
+                }
+            }
+        }
+        Operation::Calculate { offset, job } => {
+            let selected = jobs.selected(job.as_deref())?;
+            let mut total_gross = 0.0;
+            let mut total_projected = 0.0;
+            let mut total_net = Some(0.0);
+
+            for (job, wage_and_bonuses) in &selected {
+                let salery_period =
+                    salery_period_from_offset(wage_and_bonuses, offset.unwrap_or(0));
+
+                let payslip =
+                    payslip_from_period(&*db, job, wage_and_bonuses, &salery_period, EARNED)?;
+                let projected =
+                    payslip_from_period(&*db, job, wage_and_bonuses, &salery_period, PROJECTED)?;
+                let duration_worked = payslip.duration_worked();
+
+                if selected.len() > 1 {
+                    println!("==== Job: {} ====\n", job);
+                }
+                println!(
+                    "Salery period: {} - {}\n",
+                    salery_period.start().date(),
+                    salery_period.last_day()
+                );
+                println!("{}\n", payslip);
+                println!(
+                    "You have worked for: {} hours and {} minutes 
+                    \nYou have earned {:.2} kr.",
+                    duration_worked.num_hours(),
+                    duration_worked.num_minutes() % 60,
+                    payslip.salery()
+                );
 
-            for row in db
-                .prepare(format!(
-                    "select * from {} {} {}",
-                    db.table(),
-                    if !all {
-                        format!(
-                            "where shift_start <= {:#?} and shift_end >= {:#?}",
-                            salery_period.end().sql_format(),
-                            salery_period.start().sql_format()
-                        )
-                    } else {
-                        String::new()
-                    },
-                    if *sort {
-                        "order by shift_start desc"
-                    } else {
-                        ""
+                let planned_shifts = projected.shifts().len() - payslip.shifts().len();
+                if planned_shifts > 0 {
+                    println!(
+                        "With the {} planned shifts you will have earned {:.2} kr. by the end of the period",
+                        planned_shifts,
+                        projected.salery()
+                    );
+                }
+
+                total_gross += payslip.salery();
+                total_projected += projected.salery();
+                match wage_and_bonuses.tax() {
+                    Some(tax) => {
+                        let net = net_pay(
+                            tax,
+                            payslip.salery(),
+                            wage_and_bonuses.period(),
+                            &salery_period,
+                        );
+                        println!("\nEstimated net pay\n{}", net);
+                        total_net = total_net.map(|total| total + net.net());
                     }
-                ))
-                .unwrap()
-                .into_iter()
-                .map(|row| row.unwrap())
-            {
+                    None => total_net = None,
+                }
+                println!();
+            }
+
+            if selected.len() > 1 {
                 println!(
-                    "id: {} | shift start: {} | shift end: {}",
-                    row.read::<i64, _>("id"),
-                    row.read::<&str, _>("shift_start"),
-                    row.read::<&str, _>("shift_end")
+                    "==== All jobs ====\n\nYou have earned {:.2} kr. in total",
+                    total_gross
                 );
+                if total_projected != total_gross {
+                    println!(
+                        "With the planned shifts you will have earned {:.2} kr. in total",
+                        total_projected
+                    );
+                }
+                if let Some(total_net) = total_net {
+                    println!("Estimated net pay in total: {:.2} kr.", total_net);
+                }
             }
         }
-        Operation::Calculate { offset } => {
-            let salery_period = match offset {
-                Some(offset) => salery_period_from_offset(&wage_and_bonuses, *offset),
-                None => current_salery_period(&wage_and_bonuses),
-            };
+        Operation::Forecast { periods, job } => {
+            let selected = jobs.selected(job.as_deref())?;
+            let mut total_projected = 0.0;
+
+            for (job, wage_and_bonuses) in &selected {
+                if selected.len() > 1 {
+                    println!("Job: {}", job);
+                }
+
+                let first = current_salery_period(wage_and_bonuses);
+                for period in forecast(&*db, job, wage_and_bonuses, first, *periods)? {
+                    let salery_period = period.salery_period();
+                    total_projected += period.projected();
+
+                    println!(
+                        "{} - {} | earned: {:>10.2} kr. | planned: {:>10.2} kr. | projected: {:>10.2} kr.{}",
+                        salery_period.start().date(),
+                        salery_period.last_day(),
+                        period.earned(),
+                        period.planned(),
+                        period.projected(),
+                        match wage_and_bonuses.tax() {
+                            Some(tax) => format!(
+                                " | net: {:>10.2} kr.",
+                                net_pay(
+                                    tax,
+                                    period.projected(),
+                                    wage_and_bonuses.period(),
+                                    salery_period
+                                )
+                                .net()
+                            ),
+                            None => "".to_string(),
+                        }
+                    );
+                }
+                println!();
+            }
+
+            if selected.len() > 1 {
+                println!(
+                    "Projected for all jobs in total: {:.2} kr.",
+                    total_projected
+                );
+            }
+        }
+        Operation::AddJob { name } => {
+            if jobs.get(name).is_some() {
+                return Err(Error::Validation(format!(
+                    "There is already a job called {}",
+                    name
+                )));
+            }
 
-            let duration_worked = duration_worked(&db, &salery_period);
+            println!("Setting up the job: {}", name);
+            jobs.insert(name.to_string(), setup_wage_bonuses());
+            jobs.save(&wage_bonuses_path)?;
+            println!(
+                "\nAdded the job: {}. Use --job {} to add shifts to it",
+                name, name
+            );
+        }
+        Operation::AddRate {
+            effective_from,
+            base_rate,
+            job,
+        } => {
+            let job = jobs.single(job.as_deref())?.to_string();
+            let effective_from = parse_date(effective_from)?;
+            let wage_and_bonuses = jobs.job_mut(&job)?;
+            wage_and_bonuses.add_rate_change(RateChange::new(effective_from, *base_rate));
 
             println!(
-                "You have worked for: {} hours and {} minutes 
-                \nYou have earned {:.2} kr.",
-                duration_worked.num_hours(),
-                duration_worked
-                    .checked_sub(&Duration::hours(duration_worked.num_hours()))
-                    .unwrap()
-                    .num_minutes(),
-                calculate_salery_from_period(&db, &wage_and_bonuses, salery_period)
-            )
+                "From {} the base rate is {:.2} kr.\n\nRate changes:",
+                effective_from, base_rate
+            );
+            for change in wage_and_bonuses.rate_changes() {
+                println!(
+                    "{} | base rate: {:.2} kr.",
+                    change.effective_from(),
+                    change.base_rate()
+                );
+            }
+
+            jobs.save(&wage_bonuses_path)?;
         }
+        Operation::HolidayPay { periods, job } => {
+            for (job, wage_and_bonuses) in jobs.selected(job.as_deref())? {
+                if jobs.len() > 1 {
+                    println!("Job: {}", job);
+                }
 
+                let unit = match wage_and_bonuses.holiday_pay() {
+                    HolidayPay::Allowance { percentage } => {
+                        println!("Holiday allowance (feriegodtgørelse) of {}%\n", percentage);
+                        "kr."
+                    }
+                    HolidayPay::Salaried { days_pr_month } => {
+                        println!("Paid holiday of {} days pr month\n", days_pr_month);
+                        "days"
+                    }
+                };
+
+                let last = current_salery_period(wage_and_bonuses);
+                for period in holiday_pay_history(&*db, job, wage_and_bonuses, &last, *periods)? {
+                    println!(
+                        "{} - {} | gross: {:>10.2} kr. | accrued: {:>8.2} {} | balance: {:>9.2} {}",
+                        period.salery_period().start().date(),
+                        period.salery_period().last_day(),
+                        period.gross(),
+                        period.accrued(),
+                        unit,
+                        period.balance(),
+                        unit
+                    );
+                }
+                println!();
+            }
+        }
         Operation::Remove { id } => {
-            db.remove_shift(*id).unwrap();
+            db.remove_shift(i64::from(*id))?;
             println!("succesfully deleted shift with the id of: {}", id);
         }
 
         Operation::Add {
             start,
             end,
+            template,
+            date,
             break_duration,
+            break_start,
+            job,
+            status,
         } => {
-            let shift_start = parse_naivedatetime_from_str(&start).unwrap();
-            let shift_end = parse_naivedatetime_from_str(&end).unwrap();
-            db.add_shift(shift_start, shift_end, *break_duration)
-                .unwrap();
+            let job = jobs.single(job.as_deref())?;
+            let wage_and_bonuses = jobs.job(job)?;
+            let (mut shift, break_minutes) = match (template, date) {
+                (Some(template), Some(date)) => {
+                    let template = find_template(wage_and_bonuses, job, template)?;
+                    (
+                        template.shift_on(parse_date(date)?),
+                        break_duration.or(template.break_minutes()),
+                    )
+                }
+                _ => (
+                    Shift::new(
+                        // clap requires the start and end without a template
+                        parse_naivedatetime_from_str(start.as_deref().unwrap_or_default())?,
+                        parse_naivedatetime_from_str(end.as_deref().unwrap_or_default())?,
+                    ),
+                    *break_duration,
+                ),
+            };
+            let break_start = break_start
+                .as_ref()
+                .map(|s| parse_time_during_shift(s, shift.start()))
+                .transpose()?;
+
+            let break_duration = wage_and_bonuses.break_or_rule(&shift, break_minutes);
+            shift.add_break(break_duration, break_start);
+            shift.set_status(*status);
+            db.add_shift(&shift, job)?;
             println!(
-                "Added shift that started at: {} and ended at: {}, break is: {:#?}",
-                shift_start, shift_end, break_duration
+                "Added {} shift at {} that started at: {} and ended at: {}, break is: {} minutes",
+                status,
+                job,
+                shift.start(),
+                shift.end(),
+                break_duration.num_minutes()
+            );
+        }
+        Operation::Schedule {
+            template,
+            days,
+            from,
+            to,
+            job,
+            yes,
+            status,
+        } => {
+            let job = jobs.single(job.as_deref())?;
+            let wage_and_bonuses = jobs.job(job)?;
+            let template_name = template;
+            let template = find_template(wage_and_bonuses, job, template_name)?;
+            let days: Vec<Weekday> = days
+                .iter()
+                .map(|day| {
+                    day.trim()
+                        .parse()
+                        .map_err(|_| Error::Parse(format!("{} is not a day of the week", day)))
+                })
+                .collect::<Result<_>>()?;
+
+            let mut shifts: Vec<Shift> = Vec::new();
+            let mut existing: Vec<Shift> = Vec::new();
+            for date in dates_on_weekdays(parse_date(from)?, parse_date(to)?, &days) {
+                let mut shift = wage_and_bonuses.shift_from_template(template, date);
+                shift.set_status(*status);
+                if db.has_shift(job, shift.start(), shift.end())? {
+                    existing.push(shift);
+                } else {
+                    shifts.push(shift);
+                }
+            }
+
+            for shift in &existing {
+                println!(
+                    "{} already has a shift from {} to {}, it is skipped",
+                    job,
+                    shift.start(),
+                    shift.end()
+                );
+            }
+            if shifts.is_empty() {
+                if existing.is_empty() {
+                    println!("None of the days are between {} and {}", from, to);
+                }
+                return Ok(());
+            }
+
+            if !yes {
+                println!(
+                    "These {} {} shifts will be added to {} from the template {}:",
+                    shifts.len(),
+                    status,
+                    job,
+                    template_name
+                );
+                for shift in &shifts {
+                    println!(
+                        "{} | shift start: {} | shift end: {} | break: {} minutes",
+                        shift.start().weekday(),
+                        shift.start(),
+                        shift.end(),
+                        shift.break_duration().num_minutes()
+                    );
+                }
+                println!("\nDo you want to add them? [y/n]");
+
+                if !confirmed()? {
+                    println!("No shifts were added");
+                    return Ok(());
+                }
+            }
+
+            db.add_shifts(&shifts, job)?;
+            println!("Added {} shifts to {}", shifts.len(), job);
+        }
+        Operation::Import {
+            file,
+            start_column,
+            end_column,
+            date_column,
+            break_column,
+            job_column,
+            status_column,
+            delimiter,
+            format,
+            job,
+            dry_run,
+        } => {
+            let settings = ImportSettings {
+                start_column: start_column.to_string(),
+                end_column: end_column.to_string(),
+                date_column: date_column.clone(),
+                break_column: break_column.clone(),
+                job_column: job_column.clone(),
+                status_column: status_column.clone(),
+                delimiter: *delimiter,
+                formats: format.clone(),
+                // with several jobs, rows are only given a job when --job says which
+                job: match (job, job_column) {
+                    (None, Some(_)) if jobs.len() > 1 => None,
+                    _ => Some(jobs.single(job.as_deref())?.to_string()),
+                },
+            };
+            let text = read_file(file)?;
+            let (shifts, errors) = read_shifts(&text, &settings, &jobs);
+
+            for error in &errors {
+                println!("{}", error);
+            }
+
+            let outcome = add_imported_shifts(&mut *db, &shifts, *dry_run)?;
+            print_import(&outcome, errors.len(), *dry_run);
+        }
+        Operation::ImportIcs {
+            file,
+            summary,
+            from,
+            to,
+            job,
+            status,
+            dry_run,
+        } => {
+            let job = jobs.single(job.as_deref())?;
+            let wage_and_bonuses = jobs.job(job)?;
+            let text = read_file(file)?;
+            let (events, errors) = read_events(&text);
+
+            for error in &errors {
+                println!("{}", error);
+            }
+
+            let range = days_between(
+                from.as_deref().map(parse_date).transpose()?,
+                to.as_deref().map(parse_date).transpose()?,
+            );
+            let shifts = shifts_from_events(
+                events,
+                summary.as_deref(),
+                &range,
+                job,
+                wage_and_bonuses,
+                *status,
+            );
+
+            let outcome = add_imported_shifts(&mut *db, &shifts, *dry_run)?;
+            print_import(&outcome, errors.len(), *dry_run);
+        }
+        Operation::Export {
+            format,
+            all,
+            offset,
+            job,
+            pay,
+            output,
+        } => {
+            let mut shifts = Vec::new();
+            for (job, wage_and_bonuses) in jobs.selected(job.as_deref())? {
+                let salery_period =
+                    salery_period_from_offset(wage_and_bonuses, offset.unwrap_or(0));
+
+                shifts.extend(exported_shifts(
+                    &*db,
+                    job,
+                    wage_and_bonuses,
+                    (!all).then_some(&salery_period),
+                    *pay || *format == ExportFormat::Ics,
+                )?);
+            }
+
+            let exported = export(&shifts, *format)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(path, exported)
+                        .map_err(|err| Error::Io(format!("couldn't write {}: {}", path, err)))?;
+                    println!("Exported {} shifts to {}", shifts.len(), path);
+                }
+                None => print!("{}", exported),
+            }
+        }
+        Operation::ClockIn { job } => {
+            let job = jobs.single(job.as_deref())?;
+            if let Some((_, open_job, start)) = db.open_shift()? {
+                warn_if_left_open(start);
+                return Err(Error::Validation(format!(
+                    "You are already clocked in at {} since {}, clock out first",
+                    open_job, start
+                )));
+            }
+
+            let start = now();
+            db.clock_in(start, job)?;
+            println!("Clocked in at {} at: {}", job, start);
+        }
+        Operation::ClockOut {
+            break_duration,
+            break_start,
+        } => {
+            let Some((id, job, start)) = db.open_shift()? else {
+                return Err(Error::Validation("You are not clocked in".to_string()));
+            };
+            warn_if_left_open(start);
+
+            let break_start = break_start
+                .as_ref()
+                .map(|s| parse_time_during_shift(s, start))
+                .transpose()?;
+            let mut shift = Shift::new(start, now());
+            let break_duration = jobs.job(&job)?.break_or_rule(&shift, *break_duration);
+            shift.add_break(break_duration, break_start);
+            shift.set_id(id);
+            db.update_shift(&shift)?;
+
+            let worked = shift.interval().duration() - break_duration;
+            println!(
+                "Clocked out at {} at: {}, after {} hours and {} minutes with a break of {} minutes",
+                job,
+                shift.end(),
+                worked.num_hours(),
+                worked.num_minutes() % 60,
+                break_duration.num_minutes()
+            );
+        }
+        Operation::Status => {
+            let Some((id, job, start)) = db.open_shift()? else {
+                println!("You are not clocked in");
+                return Ok(());
+            };
+            warn_if_left_open(start);
+
+            let wage_and_bonuses = jobs.job(&job)?;
+            let now = now();
+            let payslip = payslip_from_shifts(
+                &[Shift::new(start, now)],
+                wage_and_bonuses,
+                &SaleryPeriod::new(start, now),
+            );
+            let worked = payslip.duration_worked();
+
+            println!(
+                "Clocked in at {} since {} (id: {})\n\n{}\n\nYou have worked for {} hours and {} minutes and earned {:.2} kr. so far, before any break",
+                job,
+                start,
+                id,
+                payslip,
+                worked.num_hours(),
+                worked.num_minutes() % 60,
+                payslip.salery()
             );
         }
         Operation::DropDatabase => {
             println!("This action will delete all entries in the database, meaning all data will be lost.\nAre you sure you want to continue? [y/n]");
-            let mut response_buffer = String::new();
-            std::io::stdin()
-                .read_line(&mut response_buffer)
-                .expect("couldnt read input");
-
-            if response_buffer.trim().to_uppercase() == "Y".to_string() {
-                db.drop_table();
+            if confirmed()? {
+                db.remove_all()?;
                 println!("Succesfully deleted all data");
             } else {
                 println!("The data is safe!");
             }
         }
-        Operation::EditShift { id, start, end } => {
-            let start = match start.as_ref() {
-                Some(s) => Some(parse_naivedatetime_from_str(s).unwrap()),
-                None => None,
-            };
-            let end = match end.as_ref() {
-                Some(s) => Some(parse_naivedatetime_from_str(s).unwrap()),
-                None => None,
-            };
-            db.edit_shift(*id, &start, &end).unwrap();
+        // handled before the shifts were opened
+        Operation::Db { .. } => {}
+        Operation::EditShift {
+            id,
+            start,
+            end,
+            break_duration,
+            break_start,
+            status,
+        } => {
+            let start = start
+                .as_deref()
+                .map(parse_naivedatetime_from_str)
+                .transpose()?;
+            let end = end
+                .as_deref()
+                .map(parse_naivedatetime_from_str)
+                .transpose()?;
+            let break_start = break_start
+                .as_deref()
+                .map(parse_naivedatetime_from_str)
+                .transpose()?;
+            db.edit_shift(
+                i64::from(*id),
+                &start,
+                &end,
+                break_duration,
+                &break_start,
+                status,
+            )?;
 
             println!(
-                "Edit succesfull! \n\nChanges:{}{}",
+                "Edit succesfull! \n\nChanges:{}{}{}{}{}",
                 if let Some(start) = start {
                     format!("\nshift_start = {}", start)
                 } else {
@@ -147,7 +647,147 @@ fn main() {
                 } else {
                     "".to_string()
                 },
+                if let Some(minutes) = break_duration {
+                    format!("\nbreak = {} minutes", minutes)
+                } else {
+                    "".to_string()
+                },
+                if let Some(break_start) = break_start {
+                    format!("\nbreak_start = {}", break_start)
+                } else {
+                    "".to_string()
+                },
+                if let Some(status) = status {
+                    format!("\nstatus = {}", status)
+                } else {
+                    "".to_string()
+                },
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Shows the version of the database, or upgrades it
+fn manage_database(db: &Database, operation: &DbOperation) -> Result<()> {
+    let DbOperation::Migrate { status } = operation;
+    if *status {
+        println!("The database is at version {}", db.schema_version()?);
+        for (migration, applied) in db.migrations()? {
+            println!(
+                "{:>3} {:<8} {}",
+                migration.version(),
+                if applied { "applied" } else { "pending" },
+                migration.description()
             );
         }
+        return Ok(());
+    }
+
+    let upgrade = db.migrate()?;
+    if upgrade.applied().is_empty() {
+        println!("The database is up to date at version {}", upgrade.to());
+    }
+    print_upgrade(&upgrade);
+    for migration in upgrade.applied() {
+        println!(
+            "Applied {} {}",
+            migration.version(),
+            migration.description()
+        );
+    }
+
+    Ok(())
+}
+
+/// Tells when an existing database was upgraded, and where the backup of it is
+fn print_upgrade(upgrade: &Upgrade) {
+    if let Some(backup) = upgrade.backup() {
+        println!(
+            "Upgraded the database from version {} to {}, a backup is kept in {}",
+            upgrade.from(),
+            upgrade.to(),
+            backup.display()
+        );
     }
 }
+
+/// Shifts are rarely longer than a day, so one that has been open that long was probably forgotten
+fn warn_if_left_open(start: NaiveDateTime) {
+    if now() - start > Duration::days(1) {
+        println!(
+            "Warning: the shift started at {} has been open for more than a day. If you forgot to clock out, use edit-shift to correct its end",
+            start
+        );
+    }
+}
+
+/// Shows the skipped and added shifts of an import, or the shifts that would be added in a dry run
+fn print_import(outcome: &ImportOutcome, bad_rows: usize, dry_run: bool) {
+    for (imported, duplicate) in &outcome.duplicates {
+        println!(
+            "line {}: skipped the shift at {} from {} to {}, it is already {}",
+            imported.line,
+            imported.job,
+            imported.shift.start(),
+            imported.shift.end(),
+            match duplicate {
+                Duplicate::InFile => "in the file",
+                Duplicate::InStore => "in the database",
+            }
+        );
+    }
+
+    if dry_run {
+        for imported in &outcome.added {
+            println!(
+                "line {}: would add {} shift at {} from {} to {}, break is: {} minutes",
+                imported.line,
+                imported.shift.status(),
+                imported.job,
+                imported.shift.start(),
+                imported.shift.end(),
+                imported.shift.break_duration().num_minutes()
+            );
+        }
+    }
+
+    println!(
+        "\n{} {} shifts, skipped {} duplicates and {} bad rows{}",
+        if dry_run { "Would add" } else { "Added" },
+        outcome.added.len(),
+        outcome.duplicates.len(),
+        bad_rows,
+        if dry_run {
+            ". Nothing was added since this was a dry run"
+        } else {
+            ""
+        }
+    );
+}
+
+fn find_template<'a>(
+    wage_and_bonuses: &'a WageAndBonuses,
+    job: &str,
+    template: &str,
+) -> Result<&'a ShiftTemplate> {
+    wage_and_bonuses
+        .template(template)
+        .ok_or_else(|| Error::Validation(format!("{} has no template called {}", job, template)))
+}
+
+fn read_file(path: &str) -> Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| Error::Io(format!("couldn't read {}: {}", path, err)))
+}
+
+/// Asks the user to answer y or n
+fn confirmed() -> Result<bool> {
+    let mut response_buffer = String::new();
+    std::io::stdin()
+        .read_line(&mut response_buffer)
+        .map_err(|err| Error::Io(format!("couldn't read the answer: {}", err)))?;
+
+    Ok(response_buffer.trim().to_uppercase() == "Y")
+}
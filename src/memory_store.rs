@@ -0,0 +1,208 @@
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    error::Result,
+    shift::{Shift, ShiftStatus},
+    store::{check_shift, no_shift, stored_id, ShiftStore},
+    time::{SQLformat, SaleryPeriod},
+};
+
+/// Keeps the shifts in memory only, eg. for tests or for calculating the pay of shifts from somewhere else
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct MemoryStore {
+    /// Ids are never reused, like in the database
+    last_id: i64,
+    shifts: Vec<StoredShift>,
+}
+
+/// A shift as it is kept, with an end once it has been clocked out
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct StoredShift {
+    id: i64,
+    job: String,
+    #[serde(with = "date_time")]
+    start: NaiveDateTime,
+    #[serde(with = "optional_date_time")]
+    end: Option<NaiveDateTime>,
+    break_minutes: i64,
+    #[serde(default, with = "optional_date_time")]
+    break_start: Option<NaiveDateTime>,
+    status: ShiftStatus,
+}
+
+impl StoredShift {
+    fn to_shift(&self) -> Option<Shift> {
+        let mut shift = Shift::new(self.start, self.end?);
+        shift.set_id(self.id);
+        shift.add_break(Duration::minutes(self.break_minutes), self.break_start);
+        shift.set_status(self.status);
+
+        Some(shift)
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(
+        &mut self,
+        job: &str,
+        start: NaiveDateTime,
+        end: Option<NaiveDateTime>,
+    ) -> &mut StoredShift {
+        self.last_id += 1;
+        self.shifts.push(StoredShift {
+            id: self.last_id,
+            job: job.to_string(),
+            start,
+            end,
+            break_minutes: 0,
+            break_start: None,
+            status: ShiftStatus::Worked,
+        });
+        // shifts are kept in order, so they can be read in the order they were worked
+        self.shifts.sort_by_key(|stored| (stored.start, stored.id));
+
+        self.shifts
+            .iter_mut()
+            .find(|stored| stored.id == self.last_id)
+            .expect("the shift was just added")
+    }
+}
+
+impl ShiftStore for MemoryStore {
+    fn add_shift(&mut self, shift: &Shift, job: &str) -> Result<()> {
+        check_shift(shift)?;
+
+        let stored = self.insert(job, shift.start(), Some(shift.end()));
+        stored.break_minutes = shift.break_duration().num_minutes();
+        stored.break_start = shift.break_start();
+        stored.status = shift.status();
+
+        Ok(())
+    }
+
+    fn update_shift(&mut self, shift: &Shift) -> Result<()> {
+        check_shift(shift)?;
+        let shift_id = stored_id(shift)?;
+
+        let stored = self
+            .shifts
+            .iter_mut()
+            .find(|stored| stored.id == shift_id)
+            .ok_or_else(|| no_shift(shift_id))?;
+        stored.start = shift.start();
+        stored.end = Some(shift.end());
+        stored.break_minutes = shift.break_duration().num_minutes();
+        stored.break_start = shift.break_start();
+        stored.status = shift.status();
+        self.shifts.sort_by_key(|stored| (stored.start, stored.id));
+
+        Ok(())
+    }
+
+    fn remove_shift(&mut self, shift_id: i64) -> Result<()> {
+        let count = self.shifts.len();
+        self.shifts.retain(|stored| stored.id != shift_id);
+
+        if self.shifts.len() == count {
+            return Err(no_shift(shift_id));
+        }
+        Ok(())
+    }
+
+    fn shifts_in_range(
+        &self,
+        job: &str,
+        salery_period: Option<&SaleryPeriod>,
+    ) -> Result<Vec<Shift>> {
+        Ok(self
+            .shifts
+            .iter()
+            .filter(|stored| stored.job == job)
+            .filter_map(StoredShift::to_shift)
+            .filter(|shift| {
+                salery_period.is_none_or(|period| {
+                    shift.start() < period.end() && shift.end() > period.start()
+                })
+            })
+            .collect())
+    }
+
+    fn shift_by_id(&self, shift_id: i64) -> Result<Option<Shift>> {
+        Ok(self
+            .shifts
+            .iter()
+            .find(|stored| stored.id == shift_id)
+            .and_then(StoredShift::to_shift))
+    }
+
+    fn clock_in(&mut self, start: NaiveDateTime, job: &str) -> Result<()> {
+        self.insert(job, start, None);
+
+        Ok(())
+    }
+
+    fn open_shift(&self) -> Result<Option<(i64, String, NaiveDateTime)>> {
+        Ok(self
+            .shifts
+            .iter()
+            .find(|stored| stored.end.is_none())
+            .map(|stored| (stored.id, stored.job.clone(), stored.start)))
+    }
+
+    fn remove_all(&mut self) -> Result<()> {
+        self.shifts.clear();
+
+        Ok(())
+    }
+}
+
+/// Times are written like in the database, eg. "2023-10-02 08:00:00"
+mod date_time {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        date_time: &NaiveDateTime,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&date_time.sql_format())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<NaiveDateTime, D::Error> {
+        let date_time = String::deserialize(deserializer)?;
+
+        NaiveDateTime::parse_from_str(&date_time, "%Y-%m-%d %H:%M:%S")
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+mod optional_date_time {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        date_time: &Option<NaiveDateTime>,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match date_time {
+            Some(date_time) => date_time::serialize(date_time, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Option<NaiveDateTime>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|date_time| {
+                NaiveDateTime::parse_from_str(&date_time, "%Y-%m-%d %H:%M:%S")
+                    .map_err(serde::de::Error::custom)
+            })
+            .transpose()
+    }
+}
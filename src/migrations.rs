@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+
+use crate::wage_bonuses::DEFAULT_JOB;
+
+/// A step in upgrading the shifts table. Every step is run once, in order, and the version of the table is stored after it
+pub struct Migration {
+    version: i64,
+    description: &'static str,
+    statements: fn(table: &str) -> String,
+}
+
+impl Migration {
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    /// The sql run for the table with the given name
+    pub fn statements(&self, table: &str) -> String {
+        (self.statements)(table)
+    }
+}
+
+/// What upgrading a table did: the versions it went from and to, the migrations that were run and where the backup was put
+pub struct Upgrade {
+    from: i64,
+    to: i64,
+    backup: Option<PathBuf>,
+    applied: Vec<&'static Migration>,
+}
+
+impl Upgrade {
+    pub fn new(
+        from: i64,
+        to: i64,
+        backup: Option<PathBuf>,
+        applied: Vec<&'static Migration>,
+    ) -> Self {
+        Self {
+            from,
+            to,
+            backup,
+            applied,
+        }
+    }
+
+    pub fn from(&self) -> i64 {
+        self.from
+    }
+
+    pub fn to(&self) -> i64 {
+        self.to
+    }
+
+    /// The copy of the database taken before upgrading it. New tables and databases in memory get none
+    pub fn backup(&self) -> Option<&Path> {
+        self.backup.as_deref()
+    }
+
+    /// The migrations that were run, none if the table was up to date
+    pub fn applied(&self) -> &[&'static Migration] {
+        &self.applied
+    }
+}
+
+/// Every migration ever made. New ones are added at the end, and old ones are never changed,
+/// since databases that already ran them won't run them again
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create the shifts table",
+        statements: |table| {
+            format!(
+                "create table if not exists {}(
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    shift_start DATETIME NOT NULL,
+                    shift_end DATETIME NOT NULL
+                );",
+                table
+            )
+        },
+    },
+    Migration {
+        version: 2,
+        description: "tag shifts with the job they were worked at",
+        // shifts from before there could be more than one job belong to the default job
+        statements: |table| {
+            format!(
+                "alter table {} add column job TEXT NOT NULL DEFAULT '{}';",
+                table, DEFAULT_JOB
+            )
+        },
+    },
+    Migration {
+        version: 3,
+        description: "store breaks with the shift",
+        statements: |table| {
+            format!(
+                "alter table {0} add column break_minutes INTEGER NOT NULL DEFAULT 0;
+                alter table {0} add column break_start DATETIME;",
+                table
+            )
+        },
+    },
+    Migration {
+        version: 4,
+        description: "allow shifts without an end while clocked in",
+        // sqlite can't drop the constraint, so the table is copied into one without it
+        statements: |table| {
+            format!(
+                "create table {0}_new(
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    shift_start DATETIME NOT NULL,
+                    shift_end DATETIME,
+                    job TEXT NOT NULL DEFAULT '{1}',
+                    break_minutes INTEGER NOT NULL DEFAULT 0,
+                    break_start DATETIME
+                );
+                insert into {0}_new (id, shift_start, shift_end, job, break_minutes, break_start)
+                    select id, shift_start, shift_end, job, break_minutes, break_start from {0};
+                drop table {0};
+                alter table {0}_new rename to {0};",
+                table, DEFAULT_JOB
+            )
+        },
+    },
+    Migration {
+        version: 5,
+        description: "mark shifts as planned, worked or cancelled",
+        statements: |table| {
+            format!(
+                "alter table {} add column status TEXT NOT NULL DEFAULT 'worked';",
+                table
+            )
+        },
+    },
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Tables from before the version was stored got their columns added when they were opened,
+/// so the version can be told from the columns the table has
+pub fn version_from_columns(columns: &[(String, bool)]) -> i64 {
+    let has = |name: &str| columns.iter().any(|(column, _)| column == name);
+    let end_required = columns
+        .iter()
+        .any(|(column, not_null)| column == "shift_end" && *not_null);
+
+    if columns.is_empty() {
+        0
+    } else if has("status") {
+        5
+    } else if !end_required {
+        4
+    } else if has("break_minutes") {
+        3
+    } else if has("job") {
+        2
+    } else {
+        1
+    }
+}
@@ -1,6 +1,10 @@
-use std::{fs, io::BufWriter, path::Path};
+use std::path::Path;
 
-use crate::wage_bonuses::{Bonus, Period, WageAndBonuses};
+use chrono::{NaiveDate, Weekday};
+
+use salery_calculator::{
+    Bonus, BreakRule, Error, Jobs, Period, Result, WageAndBonuses, DEFAULT_JOB,
+};
 
 fn get_user_input(query: &str) -> String {
     let mut buffer = String::new();
@@ -31,15 +35,53 @@ fn get_parsed_input<T: std::str::FromStr>(query: &str, err_msg: &str) -> T {
     }
 }
 
-pub fn setup_wage_bonuses_if_missing<P: AsRef<Path>>(path: P) -> Option<()> {
+/// BREAK_DURATION gave every shift a break before there were break rules in the config.
+/// It is read from the environment, or else from the .env file, so jobs without rules keep getting it
+pub fn default_break_minutes<P: AsRef<Path>>(env_file: P) -> Result<Option<i64>> {
+    let value = match std::env::var("BREAK_DURATION") {
+        Ok(value) => Some(value),
+        Err(_) => std::fs::read_to_string(env_file).ok().and_then(|text| {
+            text.lines().find_map(|line| {
+                line.trim()
+                    .strip_prefix("BREAK_DURATION=")
+                    .map(|value| value.trim().trim_matches('"').to_string())
+            })
+        }),
+    };
+
+    value
+        .map(|value| {
+            value.parse().map_err(|_| {
+                Error::Config(format!(
+                    "BREAK_DURATION should be a number of minutes, not \"{}\"",
+                    value
+                ))
+            })
+        })
+        .transpose()
+}
+
+pub fn setup_wage_bonuses_if_missing<P: AsRef<Path>>(path: P) -> Result<()> {
     let config_file = path;
 
     if Path::new(config_file.as_ref()).exists() {
-        return None;
+        return Ok(());
     }
 
-    println!("You are missing: {}. This file is needed for the program to function. Now running setup...", config_file.as_ref().file_name().unwrap().to_str().unwrap());
+    println!("You are missing: {}. This file is needed for the program to function. Now running setup...", config_file.as_ref().display());
+
+    let mut jobs = Jobs::new();
+    jobs.insert(DEFAULT_JOB.to_string(), setup_wage_bonuses());
+    jobs.save(&config_file)?;
 
+    println!("\nSetup is done. If you gave any wrong input, you can edit everything in the json document found in: {}", config_file.as_ref().display());
+
+    let _ = get_user_input("\nPress enter to continue the rest of the program");
+    Ok(())
+}
+
+/// Asks for the wage and bonuses of a job
+pub fn setup_wage_bonuses() -> WageAndBonuses {
     let base_rate: f64 = get_parsed_input(
         "\nPlease input your hourly wage. Decimals should be seperated by a period",
         "Something went wrong.",
@@ -51,33 +93,49 @@ Special means that it runs from some date during the middle of the month to just
 Example:
 If the salery period starts the 15th of april, then the end would be the 14th of may
 start = 15
-end = 14");
+end = 14
+If the start day doesn't exist in a month, eg. the 31st of april, the period starts on the last day of that month
 
-    let inp = get_user_input("\nIs your salery period special? [y/n]").to_uppercase();
+You can also be paid every week, or every fixed number of days, eg. every 14 days counted from a date you were paid for");
 
-    let period = 'outer: loop {
-        if inp == "Y" {
-            loop {
-                let start: u32 = get_parsed_input(
-                    "\nWhat is start of your salery period?",
+    let period = loop {
+        let inp =
+            get_user_input("\nHow is your salery period defined? [month/special/weekly/days]")
+                .to_lowercase();
+
+        match inp.as_str() {
+            "month" => break Period::Month,
+            "special" => break get_special_period(),
+            "weekly" => {
+                let week_start = get_user_input(
+                    "\nWhich day of the week does your salery period start on? The day should be written in english",
+                );
+                match week_start.trim().parse::<Weekday>() {
+                    Ok(week_start) => break Period::Weekly { week_start },
+                    Err(_) => println!("{} is not a day of the week", week_start),
+                }
+            }
+            "days" => {
+                let length_in_days: u32 = get_parsed_input(
+                    "\nHow many days is your salery period? eg. 14 for bi-weekly or 28 for four-weekly",
                     "Please input a number greater than 0",
                 );
-                let end = if start < 29 && start > 1 {
-                    start - 1
-                } else {
-                    println!("Start should be between the 2nd and the 29th");
-                    continue;
-                };
-                break 'outer Period::Special {
-                    start_day: start,
-                    end_day: end,
-                };
+                let anchor = get_user_input(
+                    "\nInput the first day of one of your salery periods in the format: YYYY-MM-DD",
+                );
+                match NaiveDate::parse_from_str(anchor.trim(), "%Y-%m-%d") {
+                    Ok(anchor) if length_in_days > 0 => {
+                        break Period::Days {
+                            length_in_days,
+                            anchor,
+                        }
+                    }
+                    _ => {
+                        println!("Please input a length greater than 0 and a date like 2024-01-31")
+                    }
+                }
             }
-        } else if inp == "N" {
-            break Period::Month;
-        } else {
-            println!("Please input one of the allowed responses");
-            continue;
+            _ => println!("Please input one of the allowed responses"),
         }
     };
 
@@ -85,9 +143,11 @@ end = 14");
         "\nA general bonus is a bonus that is applied every day during certain hours.
 Example
 You work night shift and get an extra 23.42 for working at night
-start = 00:00
+start = 22:00
 end = 06:00
-bonus_pr_hour = 23.42"
+bonus_pr_hour = 23.42
+
+If the end is before the start, the bonus runs past midnight and ends the following day"
     );
 
     let mut general_time_periods = Vec::new();
@@ -111,7 +171,8 @@ end = 17:00
 bonus_pr_hour = 10.47
 days = sunday
 
-If a bonus is applied on multiple days, seperate them by a comma"
+If a bonus is applied on multiple days, seperate them by a comma.
+A bonus that runs past midnight belongs to the day it starts on, eg. saturday 22:00 to 06:00 also covers sunday morning"
     );
 
     let mut day_of_week_rates = Vec::new();
@@ -128,39 +189,87 @@ If a bonus is applied on multiple days, seperate them by a comma"
         }
     }
 
-    let wage_and_bonuses =
+    let mut wage_and_bonuses =
         WageAndBonuses::new(base_rate, period, general_time_periods, day_of_week_rates);
 
-    let file = fs::File::create(config_file).expect("Couldnt create file");
-    let writer = BufWriter::new(file);
+    println!(
+        "\nA break rule gives every shift longer than some number of hours an unpaid break, unless another break is given when the shift is added.
+Example
+You have a 30 minute break on shifts longer than 6 hours
+min_shift_hours = 6
+break_minutes = 30"
+    );
+
+    loop {
+        let inp = get_user_input("\nDo you want to add a break rule? [y/n]").to_uppercase();
+        if inp == "Y" {
+            wage_and_bonuses.add_break_rule(get_break_rule())
+        } else if inp == "N" {
+            break;
+        } else {
+            println!("Please input a valic character");
+        }
+    }
+
+    wage_and_bonuses
+}
 
-    serde_json::to_writer(writer, &wage_and_bonuses)
-        .expect("couldnt parse the given inputs to json");
+fn get_break_rule() -> BreakRule {
+    let min_shift_hours: f64 = get_parsed_input(
+        "Please input how many hours a shift should be longer than to have the break",
+        "Please input a number, decimals should be seperated by a period",
+    );
+    let break_minutes: i64 = get_parsed_input(
+        "Please input how long the break is in whole minutes",
+        "Please input a whole number",
+    );
 
-    println!("\nSetup is done. If you gave any wrong input, you can edit everything in the json document found in: {}", std::env::current_exe().unwrap().display());
+    BreakRule::new(min_shift_hours, break_minutes)
+}
 
-    let _ = get_user_input("\nPress enter to continue the rest of the program");
-    Some(())
+fn get_special_period() -> Period {
+    loop {
+        let start: u32 = get_parsed_input(
+            "\nWhat is start of your salery period?",
+            "Please input a number greater than 0",
+        );
+        if (2..=31).contains(&start) {
+            return Period::Special {
+                start_day: start,
+                end_day: start - 1,
+            };
+        }
+        println!("Start should be between the 2nd and the 31st");
+    }
 }
 
 fn get_bonus() -> Bonus {
     let bonus_pr_hour: f64 = get_parsed_input("Please input the added bonus pr hour ie. the amount that will be added to your base salery", "");
-    let start: String = get_user_input(
-        "Please input what time of day the bonus starts being applied in the format: HH:MM",
-    );
-    let end: String = get_user_input(
-        "Please input what time of day the bonus stops to being applied in the format: HH:MM",
-    );
+    loop {
+        let start: String = get_user_input(
+            "Please input what time of day the bonus starts being applied in the format: HH:MM",
+        );
+        let end: String = get_user_input(
+            "Please input what time of day the bonus stops to being applied in the format: HH:MM",
+        );
 
-    Bonus::new(bonus_pr_hour, start, end, None)
+        match Bonus::new(bonus_pr_hour, start, end, None) {
+            Ok(bonus) => return bonus,
+            Err(err) => println!("{}", err),
+        }
+    }
 }
 
 fn get_day_of_week_bonus() -> Bonus {
     let mut bonus = get_bonus();
-    let days: String = get_user_input("Please input the days where this bonus applies. The days should be written in english and seperated by a comma");
+    loop {
+        let days: String = get_user_input("Please input the days where this bonus applies. The days should be written in english and seperated by a comma");
 
-    let days = days.split(",").map(|day| day.trim().to_string()).collect();
+        let days = days.split(",").map(|day| day.trim().to_string()).collect();
 
-    bonus.add_days(days);
-    bonus
+        match bonus.add_days(days) {
+            Ok(()) => return bonus,
+            Err(err) => println!("{}", err),
+        }
+    }
 }
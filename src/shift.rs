@@ -0,0 +1,175 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::time::Interval;
+
+/// A shift from when it started to when it ended, along with the break taken during it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shift {
+    /// Only shifts read from the database have an id
+    id: Option<i64>,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    break_duration: Duration,
+    break_start: Option<NaiveDateTime>,
+    status: ShiftStatus,
+}
+
+impl Shift {
+    pub fn new(start: NaiveDateTime, end: NaiveDateTime) -> Self {
+        Self {
+            id: None,
+            start,
+            end,
+            break_duration: Duration::zero(),
+            break_start: None,
+            status: ShiftStatus::Worked,
+        }
+    }
+
+    pub fn set_id(&mut self, id: i64) {
+        self.id = Some(id);
+    }
+
+    pub fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    pub fn set_status(&mut self, status: ShiftStatus) {
+        self.status = status;
+    }
+
+    pub fn status(&self) -> ShiftStatus {
+        self.status
+    }
+
+    /// Without a start, the break is taken to be in the middle of the shift
+    pub fn add_break(&mut self, duration: Duration, start: Option<NaiveDateTime>) {
+        self.break_duration = duration;
+        self.break_start = start;
+    }
+
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    pub fn end(&self) -> NaiveDateTime {
+        self.end
+    }
+
+    pub fn interval(&self) -> Interval {
+        Interval::new(self.start, self.end)
+    }
+
+    pub fn break_duration(&self) -> Duration {
+        self.break_duration
+    }
+
+    pub fn break_start(&self) -> Option<NaiveDateTime> {
+        self.break_start
+    }
+
+    /// When the break was taken, kept inside the shift
+    pub fn break_interval(&self) -> Option<Interval> {
+        if self.break_duration <= Duration::zero() {
+            return None;
+        }
+
+        let start = self
+            .break_start
+            .unwrap_or(self.start + (self.interval().duration() - self.break_duration) / 2);
+
+        Interval::new(start, start + self.break_duration).intersection(&self.interval())
+    }
+}
+
+/// Shifts from the rota are planned until they have been worked, and cancelled shifts are never paid
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShiftStatus {
+    Planned,
+    Worked,
+    Cancelled,
+}
+
+impl ShiftStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShiftStatus::Planned => "planned",
+            ShiftStatus::Worked => "worked",
+            ShiftStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl FromStr for ShiftStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "planned" => Ok(ShiftStatus::Planned),
+            "worked" => Ok(ShiftStatus::Worked),
+            "cancelled" => Ok(ShiftStatus::Cancelled),
+            _ => Err(format!(
+                "{} is not a status, use planned, worked or cancelled",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ShiftStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn datetime(hour: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 10, 2)
+            .unwrap()
+            .and_hms_opt(hour, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn break_without_a_start_is_in_the_middle_of_the_shift() {
+        let mut shift = Shift::new(datetime(8, 0), datetime(16, 0));
+        shift.add_break(Duration::minutes(30), None);
+
+        assert_eq!(
+            shift.break_interval(),
+            Some(Interval::new(datetime(11, 45), datetime(12, 15)))
+        );
+    }
+
+    #[test]
+    fn break_is_kept_inside_the_shift() {
+        let mut shift = Shift::new(datetime(8, 0), datetime(16, 0));
+        shift.add_break(Duration::minutes(30), Some(datetime(15, 45)));
+
+        assert_eq!(
+            shift.break_interval(),
+            Some(Interval::new(datetime(15, 45), datetime(16, 0)))
+        );
+    }
+
+    #[test]
+    fn status_is_read_back_the_way_it_is_stored() {
+        for status in [
+            ShiftStatus::Planned,
+            ShiftStatus::Worked,
+            ShiftStatus::Cancelled,
+        ] {
+            assert_eq!(status.as_str().parse(), Ok(status));
+        }
+        assert!("done".parse::<ShiftStatus>().is_err());
+    }
+}
@@ -0,0 +1,277 @@
+use chrono::{Duration, NaiveDateTime};
+
+use crate::{
+    error::{Error, Result},
+    shift::{Shift, ShiftStatus},
+    time::SaleryPeriod,
+};
+
+/// Where the shifts of every job are kept. Shifts get their id when they are added,
+/// and a shift that is clocked in has no end until it is clocked out
+pub trait ShiftStore {
+    /// The break is stored along with the shift, so the end of the shift stays the time work ended
+    fn add_shift(&mut self, shift: &Shift, job: &str) -> Result<()>;
+
+    /// Adds every shift, or none of them when one of them can't be added
+    fn add_shifts(&mut self, shifts: &[Shift], job: &str) -> Result<()> {
+        for shift in shifts {
+            check_shift(shift)?;
+        }
+        for shift in shifts {
+            self.add_shift(shift, job)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores the times, break and status of a shift read from the store
+    fn update_shift(&mut self, shift: &Shift) -> Result<()>;
+
+    fn remove_shift(&mut self, shift_id: i64) -> Result<()>;
+
+    /// The shifts of the job that overlap the salery period, or every shift when there is no period, ordered by their start.
+    /// Shifts that are still clocked in are left out
+    fn shifts_in_range(
+        &self,
+        job: &str,
+        salery_period: Option<&SaleryPeriod>,
+    ) -> Result<Vec<Shift>>;
+
+    /// The shift with the id, unless there is no such shift or it is still clocked in
+    fn shift_by_id(&self, shift_id: i64) -> Result<Option<Shift>>;
+
+    /// Starts a shift without an end, which is left out of the calculations until it is clocked out
+    fn clock_in(&mut self, start: NaiveDateTime, job: &str) -> Result<()>;
+
+    /// The id, job and start of the shift that is clocked in, if there is one
+    fn open_shift(&self) -> Result<Option<(i64, String, NaiveDateTime)>>;
+
+    /// Removes every shift of every job
+    fn remove_all(&mut self) -> Result<()>;
+
+    /// Whether the job already has a shift with the same start and end
+    fn has_shift(&self, job: &str, start: NaiveDateTime, end: NaiveDateTime) -> Result<bool> {
+        Ok(self
+            .shifts_in_range(job, None)?
+            .iter()
+            .any(|shift| shift.start() == start && shift.end() == end))
+    }
+
+    /// Changes the parts of the shift that are given. A shift that is clocked in can be given an end, which clocks it out
+    fn edit_shift(
+        &mut self,
+        shift_id: i64,
+        start: &Option<NaiveDateTime>,
+        end: &Option<NaiveDateTime>,
+        break_duration: &Option<i64>,
+        break_start: &Option<NaiveDateTime>,
+        status: &Option<ShiftStatus>,
+    ) -> Result<()> {
+        if start.is_none()
+            && end.is_none()
+            && break_duration.is_none()
+            && break_start.is_none()
+            && status.is_none()
+        {
+            return Err(Error::Validation(
+                "Edit the start, the end, the break and/or the status of the shift".to_string(),
+            ));
+        }
+
+        let shift = match (self.shift_by_id(shift_id)?, self.open_shift()?, end) {
+            (Some(shift), _, _) => shift,
+            (None, Some((open_id, _, open_start)), Some(end)) if open_id == shift_id => {
+                let mut shift = Shift::new(open_start, *end);
+                shift.set_id(open_id);
+                shift
+            }
+            (None, Some((open_id, _, _)), None) if open_id == shift_id => {
+                return Err(Error::Validation(
+                    "The shift is still clocked in, give it an end to edit it".to_string(),
+                ))
+            }
+            _ => return Err(no_shift(shift_id)),
+        };
+
+        let mut edited = Shift::new(start.unwrap_or(shift.start()), end.unwrap_or(shift.end()));
+        edited.set_id(shift_id);
+        edited.add_break(
+            break_duration.map_or(shift.break_duration(), Duration::minutes),
+            break_start.or(shift.break_start()),
+        );
+        edited.set_status(status.unwrap_or(shift.status()));
+
+        self.update_shift(&edited)
+    }
+}
+
+/// Every store checks the shifts it is given, so none of them keep a shift that can't be paid
+pub(crate) fn check_shift(shift: &Shift) -> Result<()> {
+    if shift.start() >= shift.end() {
+        return Err(Error::Validation(format!(
+            "The shift ends at {}, which isn't after it starts at {}",
+            shift.end(),
+            shift.start()
+        )));
+    }
+
+    if shift.break_duration() < Duration::zero() {
+        return Err(Error::Validation("The break can't be negative".to_string()));
+    }
+
+    if shift.break_duration() > shift.interval().duration() {
+        return Err(Error::Validation(
+            "The break should be shorter than the shift".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Shifts get their id when they are stored, so only shifts read from a store have one
+pub(crate) fn stored_id(shift: &Shift) -> Result<i64> {
+    shift.id().ok_or_else(|| {
+        Error::Validation("The shift hasn't been stored yet, so it has no id".to_string())
+    })
+}
+
+pub(crate) fn no_shift(shift_id: i64) -> Error {
+    Error::Validation(format!("There is no shift with the id {}", shift_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{database::Database, json_store::JsonStore, memory_store::MemoryStore};
+
+    fn datetime(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    /// The same steps are taken with every kind of store, which should all agree
+    fn add_edit_and_remove_shifts(store: &mut dyn ShiftStore) {
+        let mut night = Shift::new(datetime(30, 23), datetime(31, 3));
+        night.add_break(Duration::minutes(15), None);
+        store.add_shift(&night, "bar").unwrap();
+        store
+            .add_shift(&Shift::new(datetime(2, 8), datetime(2, 16)), "bar")
+            .unwrap();
+        store
+            .add_shift(&Shift::new(datetime(3, 8), datetime(3, 16)), "shop")
+            .unwrap();
+        store.clock_in(datetime(31, 8), "bar").unwrap();
+
+        let shifts = store.shifts_in_range("bar", None).unwrap();
+        assert_eq!(shifts.len(), 2);
+        assert_eq!(shifts[0].start(), datetime(2, 8));
+        assert_eq!(shifts[1].break_duration(), Duration::minutes(15));
+        assert!(store
+            .has_shift("bar", datetime(30, 23), datetime(31, 3))
+            .unwrap());
+        assert!(!store
+            .has_shift("shop", datetime(30, 23), datetime(31, 3))
+            .unwrap());
+
+        let october_30th = SaleryPeriod::new(datetime(30, 0), datetime(31, 0));
+        let in_range = store.shifts_in_range("bar", Some(&october_30th)).unwrap();
+        assert_eq!(in_range.len(), 1);
+        let night_id = in_range[0].id().unwrap();
+
+        store
+            .edit_shift(
+                night_id,
+                &None,
+                &None,
+                &None,
+                &None,
+                &Some(ShiftStatus::Planned),
+            )
+            .unwrap();
+        let edited = store.shift_by_id(night_id).unwrap().unwrap();
+        assert_eq!(edited.status(), ShiftStatus::Planned);
+        assert_eq!(edited.end(), datetime(31, 3));
+
+        let (open_id, open_job, open_start) = store.open_shift().unwrap().unwrap();
+        assert_eq!((open_job.as_str(), open_start), ("bar", datetime(31, 8)));
+        assert!(store.shift_by_id(open_id).unwrap().is_none());
+        store
+            .edit_shift(open_id, &None, &Some(datetime(31, 12)), &None, &None, &None)
+            .unwrap();
+        assert!(store.open_shift().unwrap().is_none());
+        assert_eq!(store.shifts_in_range("bar", None).unwrap().len(), 3);
+
+        store.remove_shift(night_id).unwrap();
+        assert!(store.shift_by_id(night_id).unwrap().is_none());
+        assert!(matches!(
+            store.remove_shift(night_id),
+            Err(Error::Validation(_))
+        ));
+        let mut negative_break = Shift::new(datetime(2, 8), datetime(2, 16));
+        negative_break.add_break(Duration::minutes(-30), None);
+        for shift in [
+            Shift::new(datetime(2, 16), datetime(2, 8)),
+            Shift::new(datetime(2, 8), datetime(2, 8)),
+            negative_break,
+        ] {
+            assert!(matches!(
+                store.add_shift(&shift, "bar"),
+                Err(Error::Validation(_))
+            ));
+        }
+
+        let backwards = Shift::new(datetime(6, 16), datetime(6, 8));
+        let week = [Shift::new(datetime(5, 8), datetime(5, 16)), backwards];
+        assert!(matches!(
+            store.add_shifts(&week, "shop"),
+            Err(Error::Validation(_))
+        ));
+        assert_eq!(store.shifts_in_range("shop", None).unwrap().len(), 1);
+        store.add_shifts(&week[..1], "shop").unwrap();
+        assert_eq!(store.shifts_in_range("shop", None).unwrap().len(), 2);
+
+        let mut missing = Shift::new(datetime(4, 8), datetime(4, 16));
+        assert!(matches!(
+            store.update_shift(&missing),
+            Err(Error::Validation(_))
+        ));
+        missing.set_id(night_id);
+        assert_eq!(store.update_shift(&missing), Err(no_shift(night_id)));
+
+        store.remove_all().unwrap();
+        assert!(store.shifts_in_range("shop", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn sqlite_store() {
+        add_edit_and_remove_shifts(&mut Database::open_or_create_db(":memory:", "shifts").unwrap());
+    }
+
+    #[test]
+    fn memory_store() {
+        add_edit_and_remove_shifts(&mut MemoryStore::new());
+    }
+
+    #[test]
+    fn json_store() {
+        let path = std::env::temp_dir().join(format!("shifts_{}.json", std::process::id()));
+        std::fs::remove_file(&path).ok();
+
+        let mut store = JsonStore::open(&path).unwrap();
+        add_edit_and_remove_shifts(&mut store);
+        store
+            .add_shift(&Shift::new(datetime(2, 8), datetime(2, 16)), "bar")
+            .unwrap();
+
+        let reopened = JsonStore::open(&path).unwrap();
+        let shifts = reopened.shifts_in_range("bar", None).unwrap();
+        assert_eq!(shifts.len(), 1);
+        assert_eq!(shifts[0].end(), datetime(2, 16));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+use crate::{
+    time::SaleryPeriod,
+    wage_bonuses::{Period, Tax},
+};
+
+/// The deductions taken from the gross pay, in the order they are taken
+pub struct NetPay {
+    gross: f64,
+    pension: f64,
+    atp: f64,
+    labour_market_contribution: f64,
+    allowance: f64,
+    withholding_tax: f64,
+}
+
+impl NetPay {
+    pub fn net(&self) -> f64 {
+        self.gross
+            - self.pension
+            - self.atp
+            - self.labour_market_contribution
+            - self.withholding_tax
+    }
+}
+
+impl fmt::Display for NetPay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = [
+            ("gross pay", self.gross),
+            ("pension, employee share", 0.0 - self.pension),
+            ("ATP", 0.0 - self.atp),
+            ("AM-bidrag", 0.0 - self.labour_market_contribution),
+            ("personal allowance used (fradrag)", self.allowance),
+            ("A-skat", 0.0 - self.withholding_tax),
+        ];
+
+        for (name, amount) in lines {
+            writeln!(f, "    {:<58}{:>12.2} kr.", name, amount)?;
+        }
+        write!(f, "    {:<58}{:>12.2} kr.", "net pay", self.net())
+    }
+}
+
+/// Pension and ATP are taken before the labour market contribution (AM-bidrag),
+/// and the withholding tax (A-skat) is paid of what is left after the personal allowance (fradrag)
+pub fn net_pay(tax: &Tax, gross: f64, period: &Period, salery_period: &SaleryPeriod) -> NetPay {
+    let pension = gross * tax.pension_percentage() / 100.0;
+    let atp = tax.atp();
+    let contribution_basis = (gross - pension - atp).max(0.0);
+    let labour_market_contribution =
+        contribution_basis * tax.labour_market_contribution_percentage() / 100.0;
+
+    let taxable = contribution_basis - labour_market_contribution;
+    let allowance = allowance_for_period(tax, period, salery_period).min(taxable);
+    let withholding_tax = (taxable - allowance) * tax.withholding_percentage() / 100.0;
+
+    NetPay {
+        gross,
+        pension,
+        atp,
+        labour_market_contribution,
+        allowance,
+        withholding_tax,
+    }
+}
+
+/// The tax card gives the allowance pr month.
+/// Periods that aren't a month get their share of the yearly allowance, by the number of days in the period
+fn allowance_for_period(tax: &Tax, period: &Period, salery_period: &SaleryPeriod) -> f64 {
+    if period.start_day().is_some() {
+        return tax.monthly_allowance();
+    }
+
+    let days = salery_period.interval().duration().num_days() as f64;
+    tax.monthly_allowance() * 12.0 * days / 365.0
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn salery_period(days: i64) -> SaleryPeriod {
+        let start = NaiveDate::from_ymd_opt(2023, 10, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        SaleryPeriod::new(start, start + chrono::Duration::days(days))
+    }
+
+    fn tax() -> Tax {
+        serde_json::from_str(
+            r#"{
+                "withholding_percentage": 37.0,
+                "monthly_allowance": 4000.0,
+                "atp": 99.0,
+                "pension_percentage": 4.0
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn deductions_are_taken_in_order() {
+        let net = net_pay(&tax(), 20000.0, &Period::Month, &salery_period(31));
+
+        assert!((net.pension - 800.0).abs() < 1e-9);
+        assert!((net.labour_market_contribution - 0.08 * 19101.0).abs() < 1e-9);
+
+        let taxable = 19101.0 * 0.92 - 4000.0;
+        assert!((net.withholding_tax - taxable * 0.37).abs() < 1e-9);
+        assert!((net.net() - (19101.0 * 0.92 - taxable * 0.37)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn allowance_is_never_more_than_the_taxable_pay() {
+        let net = net_pay(&tax(), 1000.0, &Period::Month, &salery_period(31));
+
+        assert_eq!(net.withholding_tax, 0.0);
+        assert!(net.net() > 0.0);
+    }
+
+    #[test]
+    fn allowance_is_shared_out_on_weekly_periods() {
+        let weekly = Period::Weekly {
+            week_start: chrono::Weekday::Mon,
+        };
+        let allowance = allowance_for_period(&tax(), &weekly, &salery_period(7));
+
+        assert!((allowance - 4000.0 * 12.0 * 7.0 / 365.0).abs() < 1e-9);
+    }
+}
@@ -1,13 +1,60 @@
-use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::{
+    Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday,
+};
 
-use crate::wage_bonuses::WageAndBonuses;
+use crate::{
+    error::{Error, Result},
+    wage_bonuses::{Period, WageAndBonuses},
+};
 
 pub fn calculate_shift_time(shift_start: NaiveDateTime, shift_end: NaiveDateTime) -> Duration {
-    let dur = shift_end.signed_duration_since(shift_start);
+    shift_end.signed_duration_since(shift_start)
+}
 
-    dur
+/// A half-open span of time, including the start but not the end.
+/// Two intervals that meet at midnight share no time, so nothing is counted twice or lost
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
 }
 
+impl Interval {
+    pub fn new(start: NaiveDateTime, end: NaiveDateTime) -> Self {
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    pub fn end(&self) -> NaiveDateTime {
+        self.end
+    }
+
+    pub fn duration(&self) -> Duration {
+        calculate_shift_time(self.start, self.end)
+    }
+
+    pub fn contains(&self, time: NaiveDateTime) -> bool {
+        self.start <= time && time < self.end
+    }
+
+    /// The time both intervals cover, if any
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+
+        if end > start {
+            Some(Interval::new(start, end))
+        } else {
+            None
+        }
+    }
+}
+
+/// The period a salery is paid for, running from the start of the first day
+/// up to, but not including, midnight after the last day
 #[derive(Debug)]
 pub struct SaleryPeriod {
     start: NaiveDateTime,
@@ -26,103 +73,170 @@ impl SaleryPeriod {
     pub fn end(&self) -> NaiveDateTime {
         self.end
     }
+
+    pub fn last_day(&self) -> NaiveDate {
+        (self.end - Duration::days(1)).date()
+    }
+
+    pub fn interval(&self) -> Interval {
+        Interval::new(self.start, self.end)
+    }
 }
 
 pub fn current_salery_period(wage_bonuses: &WageAndBonuses) -> SaleryPeriod {
-    let today = chrono::Local::now();
-
-    let current_month_period_end =
-        NaiveDate::from_ymd_opt(today.year(), today.month(), wage_bonuses.period().end_day())
-            .expect("the end day of the salery period is not valid");
-    let previous_month_period_start = NaiveDate::from_ymd_opt(
-        today.year(),
-        today.month(),
-        wage_bonuses.period().start_day(),
-    )
-    .expect("the start day of the salery period is not valid")
-    .checked_sub_months(Months::new(1))
-    .unwrap();
+    salery_period_from_offset(wage_bonuses, 0)
+}
 
-    let mut salery_period_end = current_month_period_end;
-    let mut salery_period_start = previous_month_period_start;
+pub fn salery_period_from_offset(wage_bonuses: &WageAndBonuses, offset: u32) -> SaleryPeriod {
+    let today = chrono::Local::now().date_naive();
 
-    if today.date_naive() > current_month_period_end {
-        salery_period_end = salery_period_end
-            .checked_add_months(Months::new(1))
-            .unwrap();
-        salery_period_start = salery_period_start
-            .checked_add_months(Months::new(1))
-            .unwrap();
-    }
+    salery_period_containing(wage_bonuses.period(), today, offset)
+}
 
-    let salery_period_start =
-        salery_period_start.and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap());
-    let salery_period_end = salery_period_end.and_time(NaiveTime::from_hms_opt(23, 59, 0).unwrap());
+/// Finds the salery period the date falls in, and then goes back the given number of periods
+pub fn salery_period_containing(period: &Period, date: NaiveDate, offset: u32) -> SaleryPeriod {
+    let (start, end) = match period {
+        Period::Special { start_day, .. } => monthly_period_containing(*start_day, date, offset),
+        Period::Month => monthly_period_containing(1, date, offset),
+        // the week the date falls in works as anchor
+        Period::Weekly { week_start } => {
+            repeating_period_containing(7, date.week(*week_start).first_day(), date, offset)
+        }
+        Period::Days {
+            length_in_days,
+            anchor,
+        } => repeating_period_containing(*length_in_days, *anchor, date, offset),
+    };
 
-    SaleryPeriod::new(salery_period_start, salery_period_end)
+    SaleryPeriod::new(start.and_time(NaiveTime::MIN), end.and_time(NaiveTime::MIN))
 }
 
-pub fn salery_period_from_offset(wage_bonuses: &WageAndBonuses, offset: u32) -> SaleryPeriod {
-    let mut salery_period = current_salery_period(wage_bonuses);
-    salery_period.start = salery_period
-        .start
-        .checked_sub_months(Months::new(offset))
-        .unwrap();
-
-    salery_period.end = salery_period
-        .end
-        .checked_sub_months(Months::new(offset))
-        .unwrap();
-
-    salery_period
-}
-
-pub fn parse_naivedatetime_from_str(date_time: &str) -> Result<NaiveDateTime, Vec<String>> {
-    // formats can be switched around to change priority. First match is returned
-    let formats = &[
-        "%d-%m-%Y %H:%M",    // danish time format
-        "%Y-%d-%m %H:%M",    // danish time format with year in front
-        "%d-%m-%Y %H:%M:%s", // danish time format with seconds
-        "%Y-%d-%m %H:%M:%s", // danish time format with year in front and seconds
-        "%Y-%m-%d %H:%M",    // american time format
-        "%Y-%m-%d %H:%M:%S", // american time format with seconds
-    ];
-    let mut errors = Vec::new();
-
-    for format in formats {
-        match NaiveDateTime::parse_from_str(date_time, format) {
-            Ok(result) => return Ok(result),
-            Err(err) => {
-                let error_message = format!(
-                    "encountered error: ({}), when trying to format input: \"{}\", according format: {}",
-                    err, date_time, format
-                );
-                errors.push(error_message);
-            }
-        }
+fn monthly_period_containing(
+    start_day: u32,
+    date: NaiveDate,
+    offset: u32,
+) -> (NaiveDate, NaiveDate) {
+    let mut month = date.with_day(1).unwrap();
+    if date < period_start_in_month(month, start_day) {
+        month = month - Months::new(1);
     }
+    let month = month - Months::new(offset);
 
-    // try adding current year to the front of input string and testing the formats again
-    // this allows adding shifts without specifying the year, which would be redundant user experience
-    let today = chrono::Local::now();
-    let date = today.date_naive();
-
-    let input = format!("{}-{}", date.year(), date_time);
-    for format in formats {
-        match NaiveDateTime::parse_from_str(&input, format) {
-            Ok(result) => return Ok(result),
-            Err(err) => {
-                let error_message = format!(
-                    "encountered error: ({}), when trying to format input: \"{}\", according format: {}",
-                    err, input, format
-                );
-                errors.push(error_message);
-            }
-        }
+    (
+        period_start_in_month(month, start_day),
+        period_start_in_month(month + Months::new(1), start_day),
+    )
+}
+
+/// Periods of a fixed length are counted from the anchor, both forwards and backwards in time
+fn repeating_period_containing(
+    length_in_days: u32,
+    anchor: NaiveDate,
+    date: NaiveDate,
+    offset: u32,
+) -> (NaiveDate, NaiveDate) {
+    let length = length_in_days as i64;
+    let periods_since_anchor = (date - anchor).num_days().div_euclid(length) - offset as i64;
+    let start = anchor + Duration::days(periods_since_anchor * length);
+
+    (start, start + Duration::days(length))
+}
+
+/// The date a period starts on in the month beginning at `first_of_month`.
+/// Start days that don't exist in short months, eg. the 31st, are moved to the last day of the month
+fn period_start_in_month(first_of_month: NaiveDate, start_day: u32) -> NaiveDate {
+    let days_in_month = ((first_of_month + Months::new(1)) - first_of_month).num_days() as u32;
+
+    first_of_month
+        .with_day(start_day.clamp(1, days_in_month))
+        .unwrap()
+}
+
+/// The formats dates and times are read in. They can be switched around to change priority, the first match is used.
+/// When the year is in front the month comes before the day, like in 2023-10-02 08:00
+pub const DATETIME_FORMATS: &[&str] = &[
+    "%d-%m-%Y %H:%M",    // danish time format
+    "%d-%m-%Y %H:%M:%S", // danish time format with seconds
+    "%Y-%m-%d %H:%M",    // iso time format
+    "%Y-%m-%d %H:%M:%S", // iso time format with seconds
+];
+
+pub fn parse_naivedatetime_from_str(date_time: &str) -> Result<NaiveDateTime> {
+    parse_naivedatetime_with_formats(date_time, DATETIME_FORMATS)
+}
+
+/// Tries every format in order, first with the input as it is and then with the current year after the day and month
+pub fn parse_naivedatetime_with_formats(
+    date_time: &str,
+    formats: &[&str],
+) -> Result<NaiveDateTime> {
+    // adding the current year allows adding shifts without specifying the year, which would be redundant user experience
+    let with_year = match date_time.split_once(' ') {
+        Some((date, time)) => format!("{}-{} {}", date, Local::now().year(), time),
+        None => date_time.to_string(),
+    };
+
+    [date_time, with_year.as_str()]
+        .iter()
+        .flat_map(|input| {
+            formats
+                .iter()
+                .map(move |format| NaiveDateTime::parse_from_str(input, format))
+        })
+        .find_map(|parsed| parsed.ok())
+        .ok_or_else(|| {
+            Error::Parse(format!(
+                "couldn't read \"{}\" as a date and time, write it like 31-01-2024 14:00",
+                date_time
+            ))
+        })
+}
+
+/// Reads a time during a shift, either as a full date and time or just the time of day like "12:30".
+/// A time of day before the start of the shift is taken to be the day after, like in a night shift
+pub fn parse_time_during_shift(time: &str, shift_start: NaiveDateTime) -> Result<NaiveDateTime> {
+    match NaiveTime::parse_from_str(time.trim(), "%H:%M") {
+        Ok(time) if time >= shift_start.time() => Ok(shift_start.date().and_time(time)),
+        Ok(time) => Ok((shift_start.date() + Duration::days(1)).and_time(time)),
+        Err(_) => parse_naivedatetime_from_str(time),
     }
+}
+
+/// Reads a day written like 2024-01-31
+pub fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+        Error::Parse(format!(
+            "couldn't read \"{}\" as a day, write it like 2024-01-31",
+            date
+        ))
+    })
+}
 
-    // If none of the formats matched
-    Err(errors)
+/// The time from the start of the first day to the end of the last day. A day that is left out puts no limit on that side
+pub fn days_between(first: Option<NaiveDate>, last: Option<NaiveDate>) -> Interval {
+    Interval::new(
+        first.map_or(NaiveDateTime::MIN, |first| first.and_time(NaiveTime::MIN)),
+        last.map_or(NaiveDateTime::MAX, |last| {
+            (last + Duration::days(1)).and_time(NaiveTime::MIN)
+        }),
+    )
+}
+
+/// Every date from the first to the last, both included, that falls on one of the days
+pub fn dates_on_weekdays(first: NaiveDate, last: NaiveDate, days: &[Weekday]) -> Vec<NaiveDate> {
+    first
+        .iter_days()
+        .take_while(|date| *date <= last)
+        .filter(|date| days.contains(&date.weekday()))
+        .collect()
+}
+
+/// The local time to the minute, used when clocking in and out
+pub fn now() -> NaiveDateTime {
+    let now = Local::now().naive_local();
+    now.with_second(0)
+        .and_then(|now| now.with_nanosecond(0))
+        .unwrap()
 }
 
 pub trait SQLformat {
@@ -139,6 +253,153 @@ impl SQLformat for NaiveDateTime {
 mod tests {
     use super::*;
 
+    fn datetime(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn period_dates(period: SaleryPeriod) -> (NaiveDate, NaiveDate) {
+        (period.start().date(), period.last_day())
+    }
+
+    #[test]
+    fn calendar_month_period() {
+        let period = salery_period_containing(&Period::Month, date(2024, 2, 10), 0);
+        assert_eq!(period_dates(period), (date(2024, 2, 1), date(2024, 2, 29)));
+
+        let period = salery_period_containing(&Period::Month, date(2023, 3, 31), 1);
+        assert_eq!(period_dates(period), (date(2023, 2, 1), date(2023, 2, 28)));
+    }
+
+    #[test]
+    fn special_period_around_the_start_day() {
+        let period = Period::Special {
+            start_day: 21,
+            end_day: 20,
+        };
+
+        let before = salery_period_containing(&period, date(2023, 10, 20), 0);
+        assert_eq!(
+            period_dates(before),
+            (date(2023, 9, 21), date(2023, 10, 20))
+        );
+
+        let on = salery_period_containing(&period, date(2023, 10, 21), 0);
+        assert_eq!(period_dates(on), (date(2023, 10, 21), date(2023, 11, 20)));
+
+        let across_new_year = salery_period_containing(&period, date(2024, 1, 5), 2);
+        assert_eq!(
+            period_dates(across_new_year),
+            (date(2023, 10, 21), date(2023, 11, 20))
+        );
+    }
+
+    #[test]
+    fn special_period_start_day_is_clamped_in_short_months() {
+        let period = Period::Special {
+            start_day: 31,
+            end_day: 30,
+        };
+
+        let february = salery_period_containing(&period, date(2024, 3, 10), 0);
+        assert_eq!(
+            period_dates(february),
+            (date(2024, 2, 29), date(2024, 3, 30))
+        );
+
+        let march = salery_period_containing(&period, date(2024, 3, 31), 0);
+        assert_eq!(period_dates(march), (date(2024, 3, 31), date(2024, 4, 29)));
+    }
+
+    #[test]
+    fn weekly_period_starts_on_the_week_start() {
+        let period = Period::Weekly {
+            week_start: Weekday::Sun,
+        };
+
+        // 2023-10-18 is a wednesday
+        let this_week = salery_period_containing(&period, date(2023, 10, 18), 0);
+        assert_eq!(
+            period_dates(this_week),
+            (date(2023, 10, 15), date(2023, 10, 21))
+        );
+
+        let last_week = salery_period_containing(&period, date(2023, 10, 15), 1);
+        assert_eq!(
+            period_dates(last_week),
+            (date(2023, 10, 8), date(2023, 10, 14))
+        );
+    }
+
+    #[test]
+    fn bi_weekly_period_counts_from_the_anchor() {
+        let period = Period::Days {
+            length_in_days: 14,
+            anchor: date(2023, 10, 2),
+        };
+
+        let after = salery_period_containing(&period, date(2023, 10, 20), 0);
+        assert_eq!(
+            period_dates(after),
+            (date(2023, 10, 16), date(2023, 10, 29))
+        );
+
+        let before = salery_period_containing(&period, date(2023, 9, 30), 0);
+        assert_eq!(period_dates(before), (date(2023, 9, 18), date(2023, 10, 1)));
+
+        let offset = salery_period_containing(&period, date(2023, 10, 20), 3);
+        assert_eq!(period_dates(offset), (date(2023, 9, 4), date(2023, 9, 17)));
+    }
+
+    #[test]
+    fn intervals_meeting_at_a_point_do_not_intersect() {
+        let first = Interval::new(datetime(1, 14), datetime(1, 22));
+        let second = Interval::new(datetime(1, 22), datetime(2, 6));
+
+        assert_eq!(first.intersection(&second), None);
+        assert_eq!(
+            first.intersection(&Interval::new(datetime(1, 20), datetime(2, 2))),
+            Some(Interval::new(datetime(1, 20), datetime(1, 22)))
+        );
+    }
+
+    #[test]
+    fn time_of_day_during_a_night_shift() {
+        let shift_start = datetime(2, 22);
+
+        assert_eq!(
+            parse_time_during_shift("23:00", shift_start),
+            Ok(datetime(2, 23))
+        );
+        assert_eq!(
+            parse_time_during_shift("02:00", shift_start),
+            Ok(datetime(3, 2))
+        );
+    }
+
+    #[test]
+    fn recurring_dates_in_a_range() {
+        let dates = dates_on_weekdays(
+            NaiveDate::from_ymd_opt(2023, 10, 2).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 10, 11).unwrap(),
+            &[Weekday::Mon, Weekday::Wed],
+        );
+
+        assert_eq!(
+            dates,
+            [2, 4, 9, 11]
+                .iter()
+                .map(|day| NaiveDate::from_ymd_opt(2023, 10, *day).unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn different_str_formats_for_parsing_naivedatetime() {
         let input = "2023-12-23 23:59";
@@ -150,4 +411,33 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn the_month_comes_before_the_day_when_the_year_is_in_front() {
+        let expected = NaiveDate::from_ymd_opt(2023, 10, 2)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+
+        assert_eq!(
+            parse_naivedatetime_from_str("2023-10-02 08:00").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_naivedatetime_from_str("02-10-2023 08:00").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_naivedatetime_from_str("2023-10-02 08:00:00").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn the_current_year_is_added_after_the_day_and_month() {
+        let parsed = parse_naivedatetime_from_str("02-10 08:00").unwrap();
+
+        assert_eq!(parsed.year(), Local::now().year());
+        assert_eq!((parsed.month(), parsed.day()), (10, 2));
+    }
 }
@@ -1,15 +1,193 @@
-use chrono::NaiveTime;
-use serde::{Deserialize, Serialize};
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Weekday};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use std::error::Error;
+use crate::{
+    error::{Error, Result},
+    holidays::{check_holiday_date, danish_holidays, matches_holiday_date},
+    shift::Shift,
+    time::Interval,
+};
+
+use std::collections::BTreeMap;
 use std::path::Path;
 
+/// The job used for shifts and config files from before there could be more than one job
+pub const DEFAULT_JOB: &str = "default";
+
+/// Every job has its own wage and bonuses.
+/// A config file with a single set of wage and bonuses holds just the default job
+#[derive(Debug, PartialEq, Default)]
+pub struct Jobs {
+    jobs: BTreeMap<String, WageAndBonuses>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum JobsFileRef<'a> {
+    Jobs {
+        jobs: &'a BTreeMap<String, WageAndBonuses>,
+    },
+    Single(&'a WageAndBonuses),
+}
+
+impl Jobs {
+    pub fn new() -> Self {
+        Jobs {
+            jobs: BTreeMap::new(),
+        }
+    }
+
+    /// Every time, day and date in the file is read when it is opened, so they can't fail later on
+    pub fn open<P: AsRef<Path>>(file_path: P) -> Result<Jobs> {
+        let file_path = file_path.as_ref();
+        let config_error = |err: &dyn std::fmt::Display| {
+            Error::Config(format!("couldn't read {}: {}", file_path.display(), err))
+        };
+        let file = std::fs::File::options()
+            .read(true)
+            .open(file_path)
+            .map_err(|err| config_error(&err))?;
+        let reader = std::io::BufReader::new(file);
+
+        let file = serde_json::from_reader(reader).map_err(|err| config_error(&err))?;
+        Jobs::from_value(file).map_err(Error::Config)
+    }
+
+    /// Every job is read on its own, so the error tells which job it is in
+    fn from_value(mut file: serde_json::Value) -> std::result::Result<Jobs, String> {
+        let jobs: BTreeMap<String, serde_json::Value> =
+            match file.as_object_mut().and_then(|file| file.remove("jobs")) {
+                Some(jobs) => serde_json::from_value(jobs).map_err(|err| err.to_string())?,
+                None => BTreeMap::from([(DEFAULT_JOB.to_string(), file)]),
+            };
+
+        let jobs = jobs
+            .into_iter()
+            .map(
+                |(job, wage_and_bonuses)| match serde_json::from_value(wage_and_bonuses) {
+                    Ok(wage_and_bonuses) => Ok((job, wage_and_bonuses)),
+                    Err(err) => Err(format!("the job {}: {}", job, err)),
+                },
+            )
+            .collect::<std::result::Result<_, _>>()?;
+
+        Ok(Jobs { jobs })
+    }
+
+    /// Keeps the old layout of the file, as long as there is only the default job
+    pub fn save<P: AsRef<Path>>(&self, file_path: P) -> Result<()> {
+        let file_path = file_path.as_ref();
+        let config_error = |err: &dyn std::fmt::Display| {
+            Error::Config(format!("couldn't save {}: {}", file_path.display(), err))
+        };
+        let file = std::fs::File::create(file_path).map_err(|err| config_error(&err))?;
+        let writer = std::io::BufWriter::new(file);
+
+        let jobs_file = match self.jobs.get(DEFAULT_JOB) {
+            Some(wage_and_bonuses) if self.jobs.len() == 1 => JobsFileRef::Single(wage_and_bonuses),
+            _ => JobsFileRef::Jobs { jobs: &self.jobs },
+        };
+        serde_json::to_writer_pretty(writer, &jobs_file).map_err(|err| config_error(&err))?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, job: &str) -> Option<&WageAndBonuses> {
+        self.jobs.get(job)
+    }
+
+    pub fn get_mut(&mut self, job: &str) -> Option<&mut WageAndBonuses> {
+        self.jobs.get_mut(job)
+    }
+
+    pub fn insert(&mut self, job: String, wage_and_bonuses: WageAndBonuses) {
+        self.jobs.insert(job, wage_and_bonuses);
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Gives every shift of the jobs without break rules a break of the given minutes
+    pub fn set_default_break(&mut self, minutes: i64) {
+        for wage_and_bonuses in self.jobs.values_mut() {
+            wage_and_bonuses.breaks.default_minutes = Some(minutes);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &WageAndBonuses)> {
+        self.jobs
+            .iter()
+            .map(|(job, wage_and_bonuses)| (job.as_str(), wage_and_bonuses))
+    }
+
+    /// The wage and bonuses of the job, which can be missing when a shift belongs to a job that was removed from the config
+    pub fn job(&self, job: &str) -> Result<&WageAndBonuses> {
+        self.get(job).ok_or_else(|| no_job(job))
+    }
+
+    pub fn job_mut(&mut self, job: &str) -> Result<&mut WageAndBonuses> {
+        self.get_mut(job).ok_or_else(|| no_job(job))
+    }
+
+    /// The given job, or every job if it is left out
+    pub fn selected(&self, job: Option<&str>) -> Result<Vec<(&str, &WageAndBonuses)>> {
+        let selected: Vec<(&str, &WageAndBonuses)> = self
+            .iter()
+            .filter(|(name, _)| job.is_none_or(|job| job == *name))
+            .collect();
+
+        match job {
+            Some(job) if selected.is_empty() => Err(no_job(job)),
+            _ => Ok(selected),
+        }
+    }
+
+    /// The given job, which can only be left out when there is just one job
+    pub fn single(&self, job: Option<&str>) -> Result<&str> {
+        match (job, self.jobs.keys().next()) {
+            (Some(job), _) => self
+                .jobs
+                .get_key_value(job)
+                .map(|(job, _)| job.as_str())
+                .ok_or_else(|| no_job(job)),
+            (None, Some(job)) if self.jobs.len() == 1 => Ok(job),
+            (None, _) => Err(Error::Validation(
+                "You have more than one job, choose one of them with --job".to_string(),
+            )),
+        }
+    }
+}
+
+fn no_job(job: &str) -> Error {
+    Error::Validation(format!("There is no job called {}", job))
+}
+
+/// The wage, bonuses, salery period and rules of a single job, as written in the config file
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct WageAndBonuses {
     base_rate: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tax: Option<Tax>,
     period: Period,
     general_time_periods: Vec<Bonus>,
     day_of_week_rates: Vec<Bonus>,
+    #[serde(default)]
+    overtime: Overtime,
+    #[serde(default)]
+    holidays: Holidays,
+    #[serde(default)]
+    holiday_pay: HolidayPay,
+    #[serde(default)]
+    breaks: Breaks,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rate_changes: Vec<RateChange>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    templates: BTreeMap<String, ShiftTemplate>,
 }
 
 impl WageAndBonuses {
@@ -21,59 +199,641 @@ impl WageAndBonuses {
     ) -> Self {
         Self {
             base_rate,
+            tax: None,
             period,
             general_time_periods,
             day_of_week_rates,
+            overtime: Overtime::default(),
+            holidays: Holidays::default(),
+            holiday_pay: HolidayPay::default(),
+            breaks: Breaks::default(),
+            rate_changes: Vec::new(),
+            templates: BTreeMap::new(),
         }
     }
 
-    pub fn open<P: AsRef<Path>>(file_path: P) -> Result<WageAndBonuses, Box<dyn Error>> {
-        let file = std::fs::File::options()
-            // .create(true)
-            .read(true)
-            .open(file_path)
-            .unwrap();
-        let reader = std::io::BufReader::new(file);
+    pub fn period(&self) -> &Period {
+        &self.period
+    }
 
-        Ok(serde_json::from_reader(reader).unwrap())
+    pub fn tax(&self) -> Option<&Tax> {
+        self.tax.as_ref()
     }
 
-    pub fn period(&self) -> &Period {
-        &self.period
+    /// The base rate and bonuses in force on the given day.
+    /// Rate changes that leave out the bonuses keep the bonuses from before the change
+    pub fn rates_on(&self, date: NaiveDate) -> Rates<'_> {
+        let mut rates = Rates {
+            base_rate: self.base_rate,
+            general_time_periods: &self.general_time_periods,
+            day_of_week_rates: &self.day_of_week_rates,
+        };
+
+        let mut changes: Vec<&RateChange> = self
+            .rate_changes
+            .iter()
+            .filter(|change| change.effective_from() <= date)
+            .collect();
+        changes.sort_by_key(|change| change.effective_from());
+
+        for change in changes {
+            rates.base_rate = change.base_rate;
+            if let Some(general_time_periods) = &change.general_time_periods {
+                rates.general_time_periods = general_time_periods;
+            }
+            if let Some(day_of_week_rates) = &change.day_of_week_rates {
+                rates.day_of_week_rates = day_of_week_rates;
+            }
+        }
+
+        rates
+    }
+
+    pub fn rate_changes(&self) -> &Vec<RateChange> {
+        &self.rate_changes
+    }
+
+    /// Replaces any change already taking effect on the same day
+    pub fn add_rate_change(&mut self, change: RateChange) {
+        self.rate_changes
+            .retain(|existing| existing.effective_from() != change.effective_from());
+        self.rate_changes.push(change);
+        self.rate_changes
+            .sort_by_key(|change| change.effective_from());
+    }
+
+    pub fn overtime(&self) -> &Overtime {
+        &self.overtime
+    }
+
+    pub fn holidays(&self) -> &Holidays {
+        &self.holidays
+    }
+
+    pub fn holiday_pay(&self) -> &HolidayPay {
+        &self.holiday_pay
+    }
+
+    pub fn breaks(&self) -> &Breaks {
+        &self.breaks
+    }
+
+    pub fn add_break_rule(&mut self, rule: BreakRule) {
+        self.breaks.rules.push(rule);
+    }
+
+    pub fn template(&self, name: &str) -> Option<&ShiftTemplate> {
+        self.templates.get(name)
+    }
+
+    /// The break given for the shift, or the break from the rules of the job when it is left out
+    pub fn break_or_rule(&self, shift: &Shift, break_minutes: Option<i64>) -> Duration {
+        match break_minutes {
+            Some(minutes) => Duration::minutes(minutes),
+            None => self.breaks.automatic_break(shift.interval().duration()),
+        }
+    }
+
+    /// The shift of the template on the given date, with the break of the template or else from the break rules
+    pub fn shift_from_template(&self, template: &ShiftTemplate, date: NaiveDate) -> Shift {
+        let mut shift = template.shift_on(date);
+        shift.add_break(self.break_or_rule(&shift, template.break_minutes()), None);
+        shift
+    }
+}
+
+/// Reads a time of day like "14:00". "24:00" is read as midnight, so it can be used as the end of the day
+fn parse_time_of_day(time: &str) -> std::result::Result<NaiveTime, String> {
+    if time.trim() == "24:00" {
+        return Ok(NaiveTime::MIN);
+    }
+
+    NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| format!("\"{}\" is not a time of day like 14:00", time))
+}
+
+fn parse_weekday(day: &str) -> std::result::Result<Weekday, String> {
+    day.trim()
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a day of the week", day))
+}
+
+fn parse_date(date: &str) -> std::result::Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("\"{}\" should be a date like 2024-01-31", date))
+}
+
+/// A time of day as it is written in the config, so "24:00" is written back the way it was
+#[derive(Debug, PartialEq)]
+struct TimeOfDay {
+    written: String,
+    time: NaiveTime,
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(written: String) -> std::result::Result<Self, Self::Error> {
+        let time = parse_time_of_day(&written)?;
+        Ok(Self { written, time })
+    }
+}
+
+impl Serialize for TimeOfDay {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.written)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeOfDay {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        TimeOfDay::try_from(String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+fn deserialize_weekdays<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Vec<String>>, D::Error> {
+    let days: Option<Vec<String>> = Option::deserialize(deserializer)?;
+    for day in days.iter().flatten() {
+        parse_weekday(day).map_err(de::Error::custom)?;
+    }
+
+    Ok(days)
+}
+
+fn serialize_date<S: Serializer>(
+    date: &NaiveDate,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&date.format("%Y-%m-%d").to_string())
+}
+
+fn deserialize_date<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<NaiveDate, D::Error> {
+    parse_date(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+}
+
+fn serialize_weekday<S: Serializer>(
+    weekday: &Weekday,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    let names = [
+        "monday",
+        "tuesday",
+        "wednesday",
+        "thursday",
+        "friday",
+        "saturday",
+        "sunday",
+    ];
+    serializer.serialize_str(names[weekday.num_days_from_monday() as usize])
+}
+
+fn deserialize_holiday_dates<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<String>, D::Error> {
+    let dates: Vec<String> = Vec::deserialize(deserializer)?;
+    for date in &dates {
+        check_holiday_date(date).map_err(de::Error::custom)?;
+    }
+
+    Ok(dates)
+}
+
+/// A shift that is worked often, eg. "evening" from 14:00 to 22:00
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ShiftTemplate {
+    start: TimeOfDay,
+    end: TimeOfDay,
+    /// Leaving it out uses the break rules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    break_minutes: Option<i64>,
+}
+
+impl ShiftTemplate {
+    pub fn break_minutes(&self) -> Option<i64> {
+        self.break_minutes
+    }
+
+    /// The shift starting on the given date. If the end is not after the start, it ends the following day
+    pub fn shift_on(&self, date: NaiveDate) -> Shift {
+        let start = date.and_time(self.start.time);
+        let mut end = date.and_time(self.end.time);
+
+        if end <= start {
+            end += Duration::days(1);
+        }
+
+        Shift::new(start, end)
+    }
+}
+
+/// Whether breaks are paid, and how unpaid breaks are taken out of the pay
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct Breaks {
+    #[serde(default)]
+    paid: bool,
+    #[serde(default)]
+    deduction: BreakDeduction,
+    /// Breaks given to shifts added without one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rules: Vec<BreakRule>,
+    /// The break in minutes every shift gets when there are no rules, from the BREAK_DURATION setting.
+    /// It is never written to the config file
+    #[serde(skip)]
+    default_minutes: Option<i64>,
+}
+
+impl Breaks {
+    pub fn paid(&self) -> bool {
+        self.paid
+    }
+
+    pub fn deduction(&self) -> BreakDeduction {
+        self.deduction
+    }
+
+    /// The break of the longest rule the shift is longer than, or no break if none of them apply.
+    /// Without any rules every shift gets the default break, if there is one
+    pub fn automatic_break(&self, shift_duration: Duration) -> Duration {
+        if self.rules.is_empty() {
+            return self
+                .default_minutes
+                .map_or(Duration::zero(), Duration::minutes);
+        }
+
+        self.rules
+            .iter()
+            .filter(|rule| shift_duration > rule.min_shift_duration())
+            .max_by_key(|rule| rule.min_shift_duration())
+            .map_or(Duration::zero(), |rule| rule.break_duration())
+    }
+}
+
+/// A break every shift longer than some number of hours has, eg. 30 minutes on shifts longer than 6 hours
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct BreakRule {
+    min_shift_hours: f64,
+    break_minutes: i64,
+}
+
+impl BreakRule {
+    pub fn new(min_shift_hours: f64, break_minutes: i64) -> Self {
+        Self {
+            min_shift_hours,
+            break_minutes,
+        }
+    }
+
+    pub fn min_shift_duration(&self) -> Duration {
+        hours_to_duration(self.min_shift_hours)
+    }
+
+    pub fn break_duration(&self) -> Duration {
+        Duration::minutes(self.break_minutes)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakDeduction {
+    /// The break is taken from the hours paid at the base rate, leaving the bonuses untouched
+    #[default]
+    Base,
+    /// Every line of the payslip is cut by the share of the shift spent on break
+    Proportional,
+    /// The break is cut out of the shift at the time it was taken, along with the bonuses in force then
+    AtTime,
+}
+
+/// How holiday pay is earned. Hourly workers get a holiday allowance (feriegodtgørelse) of their pay,
+/// while salaried staff earn days of paid holiday
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum HolidayPay {
+    Allowance {
+        #[serde(default = "default_holiday_allowance")]
+        percentage: f64,
+    },
+    Salaried {
+        #[serde(default = "default_holiday_days")]
+        days_pr_month: f64,
+    },
+}
+
+impl Default for HolidayPay {
+    fn default() -> Self {
+        Self::Allowance {
+            percentage: default_holiday_allowance(),
+        }
+    }
+}
+
+fn default_holiday_allowance() -> f64 {
+    12.5
+}
+
+fn default_holiday_days() -> f64 {
+    2.08
+}
+
+/// The days that count as holidays, and the bonuses paid for working on them
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct Holidays {
+    /// Use the built-in danish public holidays, including the ones that move with easter
+    #[serde(default)]
+    danish: bool,
+    /// Either a single date like "2026-12-24" or a date every year like "12-24"
+    #[serde(default, deserialize_with = "deserialize_holiday_dates")]
+    extra_dates: Vec<String>,
+    #[serde(default)]
+    rates: Vec<HolidayBonus>,
+}
+
+impl Holidays {
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        (self.danish
+            && danish_holidays(date.year())
+                .iter()
+                .any(|(holiday, _)| *holiday == date))
+            || self
+                .extra_dates
+                .iter()
+                .any(|holiday| matches_holiday_date(holiday, date))
+    }
+
+    pub fn rates(&self) -> &Vec<HolidayBonus> {
+        &self.rates
+    }
+}
+
+/// Applied to every hour worked on a holiday, as a fixed bonus pr hour, a multiplier of the base rate or both
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct HolidayBonus {
+    bonus_pr_hour: Option<f64>,
+    /// The base rate itself is already paid for the hours, so only the part above 1 is added as a bonus
+    multiplier: Option<f64>,
+}
+
+impl HolidayBonus {
+    pub fn bonus_pr_hour(&self, base_rate: f64) -> f64 {
+        self.bonus_pr_hour.unwrap_or(0.0) + self.multiplier.map_or(0.0, |m| base_rate * (m - 1.0))
+    }
+
+    pub fn description(&self) -> String {
+        match (self.bonus_pr_hour, self.multiplier) {
+            (_, None) => "holiday bonus".to_string(),
+            (None, Some(multiplier)) => format!("holiday x{}", multiplier),
+            (Some(_), Some(multiplier)) => format!("holiday x{} and bonus", multiplier),
+        }
+    }
+}
+
+/// A new base rate, and possibly new bonuses, taking effect from a given day
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct RateChange {
+    /// YYYY-MM-DD
+    #[serde(
+        serialize_with = "serialize_date",
+        deserialize_with = "deserialize_date"
+    )]
+    effective_from: NaiveDate,
+    base_rate: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    general_time_periods: Option<Vec<Bonus>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    day_of_week_rates: Option<Vec<Bonus>>,
+}
+
+impl RateChange {
+    /// A change of the base rate only, keeping the bonuses as they were
+    pub fn new(effective_from: NaiveDate, base_rate: f64) -> Self {
+        Self {
+            effective_from,
+            base_rate,
+            general_time_periods: None,
+            day_of_week_rates: None,
+        }
+    }
+
+    pub fn effective_from(&self) -> NaiveDate {
+        self.effective_from
     }
 
     pub fn base_rate(&self) -> f64 {
         self.base_rate
     }
+}
+
+/// The base rate and bonuses in force on a day
+pub struct Rates<'a> {
+    base_rate: f64,
+    general_time_periods: &'a Vec<Bonus>,
+    day_of_week_rates: &'a Vec<Bonus>,
+}
+
+impl Rates<'_> {
+    pub fn base_rate(&self) -> f64 {
+        self.base_rate
+    }
 
     pub fn general_time_periods(&self) -> &Vec<Bonus> {
-        &self.general_time_periods
+        self.general_time_periods
     }
 
     pub fn day_of_week_rates(&self) -> &Vec<Bonus> {
-        &self.day_of_week_rates
+        self.day_of_week_rates
     }
 }
 
+/// The numbers from the tax card, and the other deductions used to estimate the net pay
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
-#[serde(untagged)]
+pub struct Tax {
+    /// Trækprocent
+    withholding_percentage: f64,
+    /// Fradrag pr. month
+    monthly_allowance: f64,
+    /// The employee share of ATP pr. salery period, in kr.
+    #[serde(default)]
+    atp: f64,
+    /// The employee share of the pension, in percent of the gross pay
+    #[serde(default)]
+    pension_percentage: f64,
+    /// AM-bidrag
+    #[serde(default = "default_labour_market_contribution")]
+    labour_market_contribution_percentage: f64,
+}
+
+fn default_labour_market_contribution() -> f64 {
+    8.0
+}
+
+impl Tax {
+    pub fn withholding_percentage(&self) -> f64 {
+        self.withholding_percentage
+    }
+
+    pub fn monthly_allowance(&self) -> f64 {
+        self.monthly_allowance
+    }
+
+    pub fn atp(&self) -> f64 {
+        self.atp
+    }
+
+    pub fn pension_percentage(&self) -> f64 {
+        self.pension_percentage
+    }
+
+    pub fn labour_market_contribution_percentage(&self) -> f64 {
+        self.labour_market_contribution_percentage
+    }
+}
+
+/// Overtime is paid for the hours worked above a threshold, either in a single day, a week or the whole salery period.
+/// Hours already paid as overtime on a day don't count towards the weekly threshold, and so on
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct Overtime {
+    daily: Option<OvertimeRule>,
+    weekly: Option<OvertimeRule>,
+    period: Option<OvertimeRule>,
+}
+
+impl Overtime {
+    pub fn daily(&self) -> Option<&OvertimeRule> {
+        self.daily.as_ref()
+    }
+
+    pub fn weekly(&self) -> Option<&OvertimeRule> {
+        self.weekly.as_ref()
+    }
+
+    pub fn period(&self) -> Option<&OvertimeRule> {
+        self.period.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct OvertimeRule {
+    threshold_hours: f64,
+    tiers: Vec<OvertimeTier>,
+}
+
+impl OvertimeRule {
+    pub fn threshold(&self) -> Duration {
+        hours_to_duration(self.threshold_hours)
+    }
+
+    pub fn tiers(&self) -> &Vec<OvertimeTier> {
+        &self.tiers
+    }
+}
+
+/// A step of overtime pay, eg. the first 3 hours above the threshold are paid 1.5 times the base rate
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct OvertimeTier {
+    /// Leaving out the hours makes the tier cover every remaining hour of overtime
+    hours: Option<f64>,
+    /// How many times the base rate an hour of overtime is paid. The base rate itself is already paid for the hours,
+    /// so only the part above 1 is added as overtime
+    multiplier: f64,
+}
+
+impl OvertimeTier {
+    pub fn hours(&self) -> Option<Duration> {
+        self.hours.map(hours_to_duration)
+    }
+
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+}
+
+fn hours_to_duration(hours: f64) -> Duration {
+    Duration::seconds((hours * 3600.0).round() as i64)
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(untagged, try_from = "PeriodFile")]
 pub enum Period {
+    /// Runs from `start_day` in one month to the day before `start_day` the following month
     Special { start_day: u32, end_day: u32 },
+    /// Runs for a week, starting on `week_start`, eg. "monday"
+    Weekly {
+        #[serde(serialize_with = "serialize_weekday")]
+        week_start: Weekday,
+    },
+    /// Runs for a fixed number of days, eg. 14 for bi-weekly, counted from an `anchor` date the period starts on
+    Days {
+        length_in_days: u32,
+        #[serde(serialize_with = "serialize_date")]
+        anchor: NaiveDate,
+    },
+    /// Runs from the first to the last day of the month
     Month,
 }
 
-impl Period {
-    pub fn start_day(&self) -> u32 {
-        match self {
-            Self::Special { start_day, .. } => return *start_day,
-            Self::Month => 0,
+/// The salery period as it is written in the config file, before its day and date are read
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PeriodFile {
+    Special { start_day: u32, end_day: u32 },
+    Weekly { week_start: String },
+    Days { length_in_days: u32, anchor: String },
+    Month,
+}
+
+impl TryFrom<PeriodFile> for Period {
+    type Error = String;
+
+    fn try_from(period: PeriodFile) -> std::result::Result<Self, Self::Error> {
+        match period {
+            PeriodFile::Special { start_day, .. } if !(1..=31).contains(&start_day) => {
+                Err(format!(
+                    "the salery period can't start on day {} of the month",
+                    start_day
+                ))
+            }
+            PeriodFile::Special { start_day, end_day } => {
+                // the period ends the day before the next one starts, which is the last day of the month for the 1st
+                let day_before_start = if start_day == 1 { 31 } else { start_day - 1 };
+                if end_day != day_before_start {
+                    return Err(format!(
+                        "the salery period starting on day {} of the month ends on day {}, not on day {}",
+                        start_day, day_before_start, end_day
+                    ));
+                }
+                Ok(Self::Special { start_day, end_day })
+            }
+            PeriodFile::Weekly { week_start } => Ok(Self::Weekly {
+                week_start: parse_weekday(&week_start)?,
+            }),
+            PeriodFile::Days {
+                length_in_days: 0, ..
+            } => Err("the salery period has to be at least 1 day long".to_string()),
+            PeriodFile::Days {
+                length_in_days,
+                anchor,
+            } => Ok(Self::Days {
+                length_in_days,
+                anchor: parse_date(&anchor)
+                    .map_err(|err| format!("the anchor of the salery period {}", err))?,
+            }),
+            PeriodFile::Month => Ok(Self::Month),
         }
     }
+}
 
-    pub fn end_day(&self) -> u32 {
+impl Period {
+    /// The day of the month monthly periods start on.
+    /// The period ends the day before the next period starts, which is the `end_day` the config file was checked to have
+    pub fn start_day(&self) -> Option<u32> {
         match self {
-            Self::Special { end_day, .. } => return *end_day,
-            _ => panic!("expected Period to be special"), // need to think of a good way to handle that...
+            Self::Special { start_day, .. } => Some(*start_day),
+            Self::Month => Some(1),
+            _ => None,
         }
     }
 }
@@ -81,31 +841,49 @@ impl Period {
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct Bonus {
     bonus_pr_hour: f64,
-    start: String,
-    end: String,
+    start: TimeOfDay,
+    end: TimeOfDay,
+    #[serde(default, deserialize_with = "deserialize_weekdays")]
     days: Option<Vec<String>>,
 }
 
 impl Bonus {
-    pub fn new(bonus_pr_hour: f64, start: String, end: String, days: Option<Vec<String>>) -> Self {
-        Self {
+    /// The times are written like "14:00", with "24:00" as midnight at the end of the day, and the days in english
+    pub fn new(
+        bonus_pr_hour: f64,
+        start: String,
+        end: String,
+        days: Option<Vec<String>>,
+    ) -> Result<Self> {
+        let mut bonus = Self {
             bonus_pr_hour,
-            start,
-            end,
-            days,
+            start: TimeOfDay::try_from(start).map_err(Error::Parse)?,
+            end: TimeOfDay::try_from(end).map_err(Error::Parse)?,
+            days: None,
+        };
+        if let Some(days) = days {
+            bonus.add_days(days)?;
         }
+
+        Ok(bonus)
     }
 
-    pub fn add_days(&mut self, days: Vec<String>) {
+    pub fn add_days(&mut self, days: Vec<String>) -> Result<()> {
+        for day in &days {
+            parse_weekday(day).map_err(Error::Parse)?;
+        }
         self.days = Some(days);
+
+        Ok(())
     }
 
     pub fn start_time(&self) -> NaiveTime {
-        NaiveTime::parse_from_str(&self.start, "%H:%M").unwrap()
+        self.start.time
     }
 
+    /// "24:00" is read as midnight at the end of the day
     pub fn end_time(&self) -> NaiveTime {
-        NaiveTime::parse_from_str(&self.end, "%H:%M").unwrap()
+        self.end.time
     }
 
     pub fn bonus_pr_hour(&self) -> f64 {
@@ -115,6 +893,39 @@ impl Bonus {
     pub fn days(&self) -> &Option<Vec<String>> {
         &self.days
     }
+
+    /// A bonus without any days applies every day
+    pub fn applies_on(&self, weekday: Weekday) -> bool {
+        match &self.days {
+            Some(days) => days
+                .iter()
+                .filter_map(|s| s.trim().parse::<Weekday>().ok())
+                .any(|day| day == weekday),
+            None => true,
+        }
+    }
+
+    /// The time the bonus is applied in, when it starts on the given date.
+    /// If the end is not after the start, the bonus runs past midnight and ends the following day
+    pub fn window_on(&self, date: NaiveDate) -> Interval {
+        let start = date.and_time(self.start_time());
+        let mut end = date.and_time(self.end_time());
+
+        if end <= start {
+            end += Duration::days(1);
+        }
+
+        Interval::new(start, end)
+    }
+
+    /// Short human readable name of the bonus, eg. "saturday bonus 14:00-23:59"
+    pub fn description(&self) -> String {
+        let (start, end) = (&self.start.written, &self.end.written);
+        match &self.days {
+            Some(days) => format!("{} bonus {}-{}", days.join("/"), start, end),
+            None => format!("bonus {}-{}", start, end),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -123,22 +934,262 @@ mod tests {
 
     #[test]
     fn bonus_convert_string_to_time() {
-        let bonus = Bonus {
-            bonus_pr_hour: 0.0,
-            start: "14:00".to_owned(),
-            end: "0".to_owned(),
-            days: None,
-        }
-        .start_time();
+        let bonus = Bonus::new(0.0, "14:00".to_owned(), "22:00".to_owned(), None)
+            .unwrap()
+            .start_time();
 
         let time = NaiveTime::from_hms_opt(14, 0, 0).unwrap();
         assert_eq!(bonus, time);
     }
 
+    #[test]
+    fn bonus_times_and_days_are_read_when_it_is_made() {
+        assert_eq!(
+            Bonus::new(20.0, "6pm".into(), "24:00".into(), None),
+            Err(Error::Parse(
+                "\"6pm\" is not a time of day like 14:00".to_string()
+            ))
+        );
+        assert!(matches!(
+            Bonus::new(
+                20.0,
+                "18:00".into(),
+                "24:00".into(),
+                Some(vec!["sundag".into()])
+            ),
+            Err(Error::Parse(_))
+        ));
+
+        let mut bonus = Bonus::new(20.0, "18:00".into(), "24:00".into(), None).unwrap();
+        assert!(bonus.add_days(vec!["fri".into()]).is_ok());
+        assert!(bonus.add_days(vec!["friyay".into()]).is_err());
+        assert_eq!(bonus.days(), &Some(vec!["fri".to_string()]));
+    }
+
+    #[test]
+    fn bonus_ending_at_24_00_runs_to_midnight() {
+        let bonus: Bonus = serde_json::from_str(
+            r#"{"bonus_pr_hour": 20.77, "start": "14:00", "end": "24:00", "days": ["saturday"]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(bonus.end_time(), NaiveTime::MIN);
+        assert!(bonus.applies_on(Weekday::Sat));
+    }
+
+    #[test]
+    fn month_period_survives_a_round_trip() {
+        let json = serde_json::to_string(&Period::Month).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<Period>(&json).unwrap(),
+            Period::Month
+        );
+    }
+
+    #[test]
+    fn repeating_periods_are_told_apart() {
+        let weekly: Period = serde_json::from_str(r#"{"week_start": "monday"}"#).unwrap();
+        let bi_weekly: Period =
+            serde_json::from_str(r#"{"length_in_days": 14, "anchor": "2026-01-05"}"#).unwrap();
+
+        assert_eq!(
+            weekly,
+            Period::Weekly {
+                week_start: Weekday::Mon
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&bi_weekly).unwrap(),
+            r#"{"length_in_days":14,"anchor":"2026-01-05"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&weekly).unwrap(),
+            r#"{"week_start":"monday"}"#
+        );
+        assert_eq!(
+            bi_weekly,
+            Period::Days {
+                length_in_days: 14,
+                anchor: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap()
+            }
+        );
+    }
+
+    #[test]
+    fn rate_changes_keep_the_bonuses_they_leave_out() {
+        let wage_and_bonuses: WageAndBonuses = serde_json::from_str(
+            r#"{
+                "base_rate": 100.0,
+                "period": null,
+                "general_time_periods": [{"bonus_pr_hour": 20.0, "start": "18:00", "end": "24:00"}],
+                "day_of_week_rates": [],
+                "rate_changes": [
+                    {"effective_from": "2024-06-01", "base_rate": 130.0, "general_time_periods": []},
+                    {"effective_from": "2024-04-01", "base_rate": 120.0}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let rates_on =
+            |month| wage_and_bonuses.rates_on(NaiveDate::from_ymd_opt(2024, month, 1).unwrap());
+
+        assert_eq!(rates_on(3).base_rate(), 100.0);
+        assert_eq!(rates_on(4).base_rate(), 120.0);
+        assert_eq!(rates_on(4).general_time_periods().len(), 1);
+        assert_eq!(rates_on(7).base_rate(), 130.0);
+        assert!(rates_on(7).general_time_periods().is_empty());
+    }
+
+    #[test]
+    fn longest_break_rule_the_shift_is_longer_than_applies() {
+        let breaks: Breaks = serde_json::from_str(
+            r#"{"rules": [{"min_shift_hours": 6, "break_minutes": 30}, {"min_shift_hours": 9, "break_minutes": 45}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(breaks.automatic_break(Duration::hours(6)), Duration::zero());
+        assert_eq!(
+            breaks.automatic_break(Duration::minutes(6 * 60 + 1)),
+            Duration::minutes(30)
+        );
+        assert_eq!(
+            breaks.automatic_break(Duration::hours(10)),
+            Duration::minutes(45)
+        );
+    }
+
+    #[test]
+    fn default_break_is_only_used_without_rules() {
+        let mut jobs = Jobs::new();
+        jobs.insert(
+            "bar".to_string(),
+            WageAndBonuses::new(100.0, Period::Month, Vec::new(), Vec::new()),
+        );
+        let mut shop = WageAndBonuses::new(100.0, Period::Month, Vec::new(), Vec::new());
+        shop.add_break_rule(BreakRule::new(6.0, 45));
+        jobs.insert("shop".to_string(), shop);
+        jobs.set_default_break(30);
+
+        let automatic_break = |job| {
+            jobs.get(job)
+                .unwrap()
+                .breaks()
+                .automatic_break(Duration::hours(4))
+        };
+        assert_eq!(automatic_break("bar"), Duration::minutes(30));
+        assert_eq!(automatic_break("shop"), Duration::zero());
+        assert!(!serde_json::to_string(jobs.get("bar").unwrap())
+            .unwrap()
+            .contains("30"));
+    }
+
+    #[test]
+    fn template_ending_after_midnight_ends_the_next_day() {
+        let template: ShiftTemplate =
+            serde_json::from_str(r#"{"start": "22:00", "end": "06:00"}"#).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+        let shift = template.shift_on(date);
+
+        assert_eq!(shift.start(), date.and_hms_opt(22, 0, 0).unwrap());
+        assert_eq!(
+            shift.end(),
+            date.succ_opt().unwrap().and_hms_opt(6, 0, 0).unwrap()
+        );
+        assert_eq!(template.break_minutes(), None);
+    }
+
+    #[test]
+    fn times_days_and_dates_that_cant_be_read_are_found_when_opened() {
+        let read = |job: &str| {
+            let job: serde_json::Value = serde_json::from_str(job).unwrap();
+            Jobs::from_value(serde_json::json!({ "jobs": { "bar": job } }))
+        };
+
+        assert_eq!(
+            read(
+                r#"{
+                "base_rate": 120.0,
+                "period": {"week_start": "monday"},
+                "general_time_periods": [{"bonus_pr_hour": 20.0, "start": "18:00", "end": "24:00", "days": null}],
+                "day_of_week_rates": [{"bonus_pr_hour": 30.0, "start": "00:00", "end": "24:00", "days": ["saturday", "sundag"]}]
+            }"#
+            ),
+            Err("the job bar: \"sundag\" is not a day of the week".to_string())
+        );
+
+        assert_eq!(
+            read(
+                r#"{"base_rate": 120.0, "period": {"week_start": "monday"}, "general_time_periods": [], "day_of_week_rates": [],
+                "templates": {"evening": {"start": "14.00", "end": "22:00"}}}"#
+            ),
+            Err("the job bar: \"14.00\" is not a time of day like 14:00".to_string())
+        );
+
+        assert_eq!(
+            read(
+                r#"{"base_rate": 120.0, "period": {"week_start": "monday"}, "general_time_periods": [], "day_of_week_rates": [],
+                "holidays": {"extra_dates": ["2026-12-24", "02-29", "31-12"]}}"#
+            ),
+            Err(
+                "the job bar: the holiday \"31-12\" should be a date like 2026-12-24 or 12-24"
+                    .to_string()
+            )
+        );
+
+        assert_eq!(
+            read(
+                r#"{"base_rate": 120.0, "period": {"length_in_days": 14, "anchor": "05-01-2026"}, "general_time_periods": [], "day_of_week_rates": []}"#
+            ),
+            Err("the job bar: the anchor of the salery period \"05-01-2026\" should be a date like 2024-01-31".to_string())
+        );
+
+        assert_eq!(
+            read(
+                r#"{"base_rate": 120.0, "period": {"length_in_days": 0, "anchor": "2026-01-05"}, "general_time_periods": [], "day_of_week_rates": []}"#
+            ),
+            Err("the job bar: the salery period has to be at least 1 day long".to_string())
+        );
+
+        assert_eq!(
+            read(
+                r#"{"base_rate": 120.0, "period": {"start_day": 21, "end_day": 25}, "general_time_periods": [], "day_of_week_rates": []}"#
+            ),
+            Err("the job bar: the salery period starting on day 21 of the month ends on day 20, not on day 25".to_string())
+        );
+    }
+
+    #[test]
+    fn config_file_with_several_jobs() {
+        let json = r#"{"jobs": {
+            "bar": {"base_rate": 120.0, "period": null, "general_time_periods": [], "day_of_week_rates": []},
+            "shop": {"base_rate": 140.0, "period": {"week_start": "monday"}, "general_time_periods": [], "day_of_week_rates": []}
+        }}"#;
+        let jobs = Jobs::from_value(serde_json::from_str(json).unwrap()).unwrap();
+
+        let names: Vec<&str> = jobs.iter().map(|(job, _)| job).collect();
+        assert_eq!(names, vec!["bar", "shop"]);
+        assert_eq!(
+            jobs.get("shop")
+                .unwrap()
+                .rates_on(NaiveDate::MIN)
+                .base_rate(),
+            140.0
+        );
+        assert_eq!(jobs.selected(None).unwrap().len(), 2);
+        assert_eq!(jobs.selected(Some("shop")).unwrap()[0].0, "shop");
+        assert_eq!(jobs.single(Some("bar")), Ok("bar"));
+        for job in [None, Some("cafe")] {
+            assert!(matches!(jobs.single(job), Err(Error::Validation(_))));
+        }
+        assert!(jobs.selected(Some("cafe")).is_err());
+    }
+
     #[test]
     fn deserialise_struct_from_json() {
         let expected = WageAndBonuses {
             base_rate: 136.74,
+            tax: None,
             period: Period::Special {
                 start_day: 21,
                 end_day: 20,
@@ -146,35 +1197,46 @@ mod tests {
             general_time_periods: vec![
                 Bonus {
                     bonus_pr_hour: 20.77,
-                    start: "18:00".to_string(),
-                    end: "23:59".to_string(),
+                    start: "18:00".to_string().try_into().unwrap(),
+                    end: "00:00".to_string().try_into().unwrap(),
                     days: None,
                 },
                 Bonus {
                     bonus_pr_hour: 28.38,
-                    start: "00:00".to_string(),
-                    end: "06:00".to_string(),
+                    start: "00:00".to_string().try_into().unwrap(),
+                    end: "06:00".to_string().try_into().unwrap(),
                     days: None,
                 },
             ],
             day_of_week_rates: vec![
                 Bonus {
                     bonus_pr_hour: 20.77,
-                    start: "14:00".to_string(),
-                    end: "24:00".to_string(),
-                    days: Some(vec!["lørdag".to_string()]),
+                    start: "14:00".to_string().try_into().unwrap(),
+                    end: "00:00".to_string().try_into().unwrap(),
+                    days: Some(vec!["saturday".to_string()]),
                 },
                 Bonus {
                     bonus_pr_hour: 28.38,
-                    start: "06:00".to_string(),
-                    end: "24:00".to_string(),
-                    days: Some(vec!["søndag".to_string()]),
+                    start: "06:00".to_string().try_into().unwrap(),
+                    end: "00:00".to_string().try_into().unwrap(),
+                    days: Some(vec!["sunday".to_string()]),
                 },
             ],
+            overtime: Overtime::default(),
+            holidays: Holidays::default(),
+            holiday_pay: HolidayPay::default(),
+            breaks: Breaks {
+                rules: vec![BreakRule::new(6.0, 30)],
+                ..Breaks::default()
+            },
+            rate_changes: Vec::new(),
+            templates: BTreeMap::new(),
         };
 
-        let thing = WageAndBonuses::open("/Wage_bonuses_map.json").unwrap();
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/Wage_bonuses_map.json");
+        let jobs = Jobs::open(path).unwrap();
 
-        assert_eq!(thing, expected);
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs.get(DEFAULT_JOB), Some(&expected));
     }
 }